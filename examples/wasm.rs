@@ -0,0 +1,29 @@
+//! Clustering without `thread_rng`, as on `wasm32-unknown-unknown`, where rand can't reach the
+//! operating system's entropy unless `getrandom`'s `js` feature is wired up.
+//!
+//! With the `thread_rng` feature off, nothing in the crate needs `getrandom`, so this builds for
+//! the browser as-is:
+//!
+//! ```text
+//! cargo build --example wasm --target wasm32-unknown-unknown --no-default-features --features std
+//! ```
+
+use kmeans::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+/// Clusters `points`, drawing all randomness from a generator seeded with `seed`, which in the
+/// browser could come from `Math.random()` or `crypto.getRandomValues()` on the JavaScript side
+fn cluster(points: Vec<(f64, f64)>, seed: u64) -> Vec<(f64, f64)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let result = KMeans::new(2).n_init(5).fit_with_rng(points, &mut rng);
+    Cluster::centroids(&result.clusters)
+}
+
+fn main() {
+    let points = vec![(1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (8.0, 4.0), (8.0, 5.0), (9.0, 6.0)];
+    println!("{:?}", cluster(points.clone(), 42));
+
+    // Any `Rng` will do, such as one the caller already has
+    let mut rng = StdRng::seed_from_u64(7);
+    println!("{:?}", Cluster::centroids(&kmeans_with_rng(2, points, &mut rng)));
+}
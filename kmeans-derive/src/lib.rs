@@ -0,0 +1,114 @@
+//! `#[derive(DataPoint)]` for structs of numeric fields, re-exported by `kmeans` with its `derive`
+//! feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Index, Member};
+
+/// Implements `kmeans::types::DataPoint` for a struct whose fields are all primitive numbers,
+/// treating each field as a coordinate.
+///
+/// The distance is the straight-line distance between the fields as `f64`s, and the mean is the
+/// mean of each field, converted back with `as` (so integer fields are truncated toward zero).
+/// Ties are broken by comparing the fields in order.
+///
+/// A field marked `#[kmeans(skip)]`, like an id or a name, isn't a coordinate. It doesn't count
+/// toward the distance, and a mean takes it from the first of the points being averaged, so its
+/// type only has to be `Clone`.
+#[proc_macro_derive(DataPoint, attributes(kmeans))]
+pub fn derive_data_point(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into()
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(syn::Error::new_spanned(&input.ident, "DataPoint can only be derived for structs"))
+    };
+
+    // Every field, and whether it's a coordinate
+    let mut members = vec![];
+    for (i, field) in fields.iter().enumerate() {
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(Index::from(i))
+        };
+        members.push((member, !is_skipped(field)?));
+    }
+    let coordinates: Vec<&Member> = members.iter().filter(|(_, c)| *c).map(|(m, _)| m).collect();
+    if coordinates.is_empty() {
+        return Err(syn::Error::new_spanned(&input.ident, "DataPoint needs at least one field that isn't skipped"));
+    }
+    let n = proc_macro2::Literal::usize_suffixed(coordinates.len());
+
+    // The struct rebuilt from `mean`, an array of the coordinates' means, and `first`, a point
+    // to take the skipped fields from
+    let mut next = 0;
+    let rebuilt: Vec<TokenStream2> = members.iter()
+        .map(|(member, coordinate)| {
+            if *coordinate {
+                next += 1;
+                let j = proc_macro2::Literal::usize_suffixed(next - 1);
+                quote!(#member: mean[#j] as _)
+            } else {
+                quote!(#member: ::core::clone::Clone::clone(&first.#member))
+            }
+        })
+        .collect();
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::kmeans::types::DataPoint for #name #ty_generics #where_clause {
+            fn dist(&self, other: &Self) -> f64 {
+                <[f64; #n] as ::kmeans::types::DataPoint>::dist(
+                    &[#(self.#coordinates as f64),*],
+                    &[#(other.#coordinates as f64),*]
+                )
+            }
+
+            fn dist_sq(&self, other: &Self) -> f64 {
+                <[f64; #n] as ::kmeans::types::DataPoint>::dist_sq(
+                    &[#(self.#coordinates as f64),*],
+                    &[#(other.#coordinates as f64),*]
+                )
+            }
+
+            fn mean(ps: &[Self]) -> ::core::option::Option<Self> {
+                let first = ps.first()?;
+                let mean: [f64; #n] = ::kmeans::types::__derived_mean(
+                    ps.iter().map(|p| [#(p.#coordinates as f64),*])
+                );
+                ::core::option::Option::Some(Self { #(#rebuilt),* })
+            }
+
+            fn tie_order(&self, other: &Self) -> ::core::cmp::Ordering {
+                <[f64; #n] as ::kmeans::types::DataPoint>::tie_order(
+                    &[#(self.#coordinates as f64),*],
+                    &[#(other.#coordinates as f64),*]
+                )
+            }
+        }
+    })
+}
+
+/// Whether the field is marked `#[kmeans(skip)]`
+fn is_skipped(field: &syn::Field) -> syn::Result<bool> {
+    let mut skipped = false;
+    for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("kmeans")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skipped = true;
+                Ok(())
+            } else {
+                Err(meta.error("unknown kmeans attribute, expected `skip`"))
+            }
+        })?;
+    }
+    Ok(skipped)
+}
@@ -0,0 +1,166 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use rand::prelude::*;
+use crate::{_kmeans, StoppingCriteria};
+use crate::metric::Euclidean;
+use crate::types::*;
+
+/// Clustering algorithm that starts with every point in one cluster and splits clusters in two
+/// until there are `k` of them.
+///
+/// Each round, the cluster with the highest `Cluster::inertia` is split by running k-means++ with
+/// a k of 2 on just its points. Splitting from the top down like this tends to give clusters of
+/// more even sizes than `kmeans`, and the order of the splits forms a hierarchy: each split's two
+/// halves replace the cluster they came from, at its place in the list and at the end.
+///
+/// A cluster of one point can't be split, so if there are fewer than `k` points, every point ends
+/// up in a cluster of its own.
+pub fn bisecting_kmeans<P: DataPoint>(k: usize, datapoints: Vec<P>) -> Vec<Cluster<P>> {
+    _bisecting(k, datapoints, &mut thread_rng())
+}
+
+fn _bisecting<P, R>(k: usize, datapoints: Vec<P>, rng: &mut R) -> Vec<Cluster<P>>
+    where P: DataPoint, R: Rng + ?Sized
+{
+    let centroid = match P::mean(&datapoints) {
+        Some(centroid) if k > 0 => centroid,
+        _ => return vec![]
+    };
+
+    let mut clusters = vec![Cluster { centroid, points: datapoints }];
+    while clusters.len() < k {
+        let target = clusters.iter().enumerate()
+            .filter(|(_, c)| c.size() > 1)
+            .max_by(|(_, a), (_, b)| a.inertia().partial_cmp(&b.inertia()).unwrap_or(core::cmp::Ordering::Equal))
+            .map(|(i, _)| i);
+        let target = match target {
+            Some(target) => target,
+            None => break
+        };
+
+        let cluster = Cluster {
+            centroid: clusters[target].centroid.clone(),
+            points: core::mem::take(&mut clusters[target].points)
+        };
+        let (first, second) = cluster._split(rng);
+        clusters[target] = first;
+        clusters.push(second);
+    }
+    clusters
+}
+
+impl<P: DataPoint> Cluster<P> {
+    /// Breaks the cluster in two by running k-means++ with a k of 2 on just its points, as each
+    /// round of `bisecting_kmeans` does.
+    ///
+    /// A cluster of fewer than two points can't be split, so it comes back whole, along with an
+    /// empty cluster with the same centroid.
+    pub fn split(self) -> (Cluster<P>, Cluster<P>) {
+        self._split(&mut thread_rng())
+    }
+
+    fn _split<R: Rng + ?Sized>(self, rng: &mut R) -> (Cluster<P>, Cluster<P>) {
+        if self.size() < 2 {
+            let empty = Cluster::new(&self.centroid);
+            return (self, empty);
+        }
+
+        let mut halves = _kmeans(2, &self.points, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &Euclidean, rng).clusters;
+        let second = halves.pop().unwrap();
+        (halves.pop().unwrap(), second)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::{kmeans_seeded, kmeans_with_rng, total_inertia};
+    use crate::types::Cluster;
+    use super::_bisecting;
+
+    fn points() -> Vec<(f64, f64)> {
+        vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ]
+    }
+
+    #[test]
+    fn compares_with_flat_kmeans() {
+        let whole = _bisecting(1, points(), &mut StdRng::seed_from_u64(0));
+        assert_eq!(whole.len(), 1);
+        assert_eq!(whole[0].points, points());
+
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            // The first split is a plain 2-means run, and later ones can only lower the inertia
+            let halves = _bisecting(2, points(), &mut rng);
+            assert!((total_inertia(&halves) - total_inertia(&kmeans_seeded(2, points(), seed))).abs() < 1e-9);
+
+            let mut last = total_inertia(&halves);
+            for k in 3..=4 {
+                let bisected = _bisecting(k, points(), &mut rng);
+                assert_eq!(bisected.len(), k);
+                assert_eq!(bisected.iter().map(Cluster::size).sum::<usize>(), points().len());
+
+                // Splits are never undone, so this can end up a little worse than flat k-means,
+                // but not far off
+                let inertia = total_inertia(&bisected);
+                let flat = total_inertia(&kmeans_seeded(k, points(), seed));
+                assert!(inertia < 1.25 * flat, "{} vs. {} for k = {}", inertia, flat, k);
+                assert!(inertia < last);
+                last = inertia;
+            }
+        }
+    }
+
+    #[test]
+    fn too_few_points() {
+        let points = vec![(1f64, 2f64), (1.0, 3.0), (8.0, 4.0)];
+        let clusters = _bisecting(5, points.clone(), &mut StdRng::seed_from_u64(0));
+
+        assert_eq!(clusters.len(), 3);
+        for cluster in clusters.iter() {
+            assert_eq!(cluster.points, vec![cluster.centroid]);
+        }
+        assert!(_bisecting(0, points, &mut StdRng::seed_from_u64(0)).is_empty());
+        assert!(_bisecting(2, Vec::<(f64, f64)>::new(), &mut StdRng::seed_from_u64(0)).is_empty());
+    }
+
+    #[test]
+    fn split_cluster() {
+        let file = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/test_data.txt")).unwrap();
+        let data: Vec<(f64, f64, f64)> = file.trim().lines()
+            .map(|line| {
+                let coords: Vec<f64> = line.split_whitespace().map(|s| s.parse().unwrap()).collect();
+                (coords[0], coords[1], coords[2])
+            })
+            .collect();
+        let by_coords = |a: &(f64, f64, f64), b: &(f64, f64, f64)| a.partial_cmp(b).unwrap();
+
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let clusters = kmeans_with_rng(2, data.clone(), &mut rng);
+            let larger = clusters.into_iter().max_by_key(Cluster::size).unwrap();
+            let mut original = larger.points.clone();
+            original.sort_by(by_coords);
+
+            let (first, second) = larger._split(&mut rng);
+            assert!(first.size() > 0 && second.size() > 0);
+            let mut combined: Vec<(f64, f64, f64)> = first.points.into_iter().chain(second.points).collect();
+            combined.sort_by(by_coords);
+            assert_eq!(combined, original);
+        }
+
+        let single = Cluster { centroid: 1.0, points: vec![1.0] };
+        assert_eq!(single.clone().split(), (single, Cluster { centroid: 1.0, points: vec![] }));
+    }
+}
@@ -0,0 +1,292 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use rand::prelude::*;
+use crate::{_best_of, _nearest, StoppingCriteria};
+use crate::metric::Euclidean;
+use crate::types::*;
+
+/// All of the settings for a k-means++ run, in one place.
+///
+/// ```
+/// # #[cfg(feature = "thread_rng")] {
+/// use kmeans::prelude::*;
+///
+/// let points = vec![(1f64, 2f64), (1.0, 3.0), (8.0, 4.0), (9.0, 5.0)];
+/// let result = KMeans::new(2).max_iter(100).tol(1e-4).seed(42).n_init(10).fit(points);
+/// assert_eq!(result.clusters.len(), 2);
+/// # }
+/// ```
+///
+/// Anything left unset behaves like `kmeans`: k-means++ initialization, no cap on rounds, no
+/// tolerance, a single run, and a fresh `thread_rng` each time `fit` is called.
+///
+/// It also holds the centroids found so far by `partial_fit`, for clustering data that arrives a
+/// batch at a time.
+#[derive(Debug, Clone)]
+pub struct KMeans<P> {
+    k: usize,
+    init: InitMethod,
+    stop: StoppingCriteria,
+    n_init: usize,
+    seed: Option<u64>,
+    retain_points: bool,
+    centroids: Vec<P>,
+    counts: Vec<usize>
+}
+
+impl<P> KMeans<P> {
+    /// Settings for making `k` clusters, with everything else at its default
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            init: InitMethod::default(),
+            stop: StoppingCriteria::default(),
+            n_init: 1,
+            seed: None,
+            retain_points: true,
+            centroids: vec![],
+            counts: vec![]
+        }
+    }
+
+    /// Chooses the starting centroids with `init`, as in `kmeans_with_init`
+    pub fn init(mut self, init: InitMethod) -> Self {
+        self.init = init;
+        self
+    }
+
+    /// Stops after `max_iter` rounds of recentering and reassignment, as in `kmeans_with_max_iter`
+    pub fn max_iter(mut self, max_iter: usize) -> Self {
+        self.stop.max_iter = max_iter;
+        self
+    }
+
+    /// Stops once no centroid moves by `tol` or more in a round, as in `kmeans_with_tol`
+    pub fn tol(mut self, tol: f64) -> Self {
+        self.stop.tol = tol;
+        self
+    }
+
+    /// Runs `n_init` times and keeps the lowest-inertia clustering, as in `kmeans_best_of`
+    pub fn n_init(mut self, n_init: usize) -> Self {
+        self.n_init = n_init;
+        self
+    }
+
+    /// Draws all randomness from a generator seeded with `seed`, so that every `fit` on the same
+    /// points gives the same result
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Whether `fit` keeps each cluster's points, which it does by default. With `false`, every
+    /// cluster comes back with no `points`, so the result holds little more than the centroids
+    /// and `labels`, however big the points are.
+    ///
+    /// `predict` still works, as it only needs the centroids, and `KMeansResult::sizes` and
+    /// `KMeansResult::inertia` still describe the whole clustering. Anything that looks at a
+    /// cluster's members, like `Cluster::size`, `Cluster::inertia` or `Cluster::outliers`, finds
+    /// none.
+    pub fn with_retained_points(mut self, retain_points: bool) -> Self {
+        self.retain_points = retain_points;
+        self
+    }
+
+    /// The centroids found by `partial_fit` so far, or none if it hasn't been called yet
+    pub fn centroids(&self) -> &[P] {
+        &self.centroids
+    }
+
+    /// How many points `partial_fit` has assigned to each of the `centroids` so far
+    pub fn counts(&self) -> &[usize] {
+        &self.counts
+    }
+}
+
+impl<P: DataPoint> KMeans<P> {
+    /// Clusters the `datapoints` with these settings
+    #[cfg(feature = "thread_rng")]
+    pub fn fit(&self, datapoints: Vec<P>) -> KMeansResult<P> {
+        match self.seed {
+            Some(seed) => self.fit_with_rng(datapoints, &mut StdRng::seed_from_u64(seed)),
+            None => self.fit_with_rng(datapoints, &mut thread_rng())
+        }
+    }
+
+    /// Clusters the `datapoints` with these settings, drawing all randomness from `rng` and
+    /// ignoring any `seed`.
+    ///
+    /// This is the way to fit without the `thread_rng` feature, such as on
+    /// `wasm32-unknown-unknown`, where there's no thread-local generator to fall back on.
+    pub fn fit_with_rng<R: Rng + ?Sized>(&self, datapoints: Vec<P>, rng: &mut R) -> KMeansResult<P> {
+        let mut result = _best_of(self.k, datapoints, self.n_init, self.init, self.stop, rng);
+        if !self.retain_points {
+            for cluster in result.clusters.iter_mut() {
+                cluster.points = vec![];
+            }
+        }
+        result
+    }
+}
+
+impl<P: WeightedMean> KMeans<P> {
+    /// Updates the centroids with another `batch` of points, keeping what was learned from the
+    /// batches before it.
+    ///
+    /// The first call clusters its batch as `fit` would, and keeps the centroids along with how
+    /// many points each one got. Every call after that assigns each point in the batch to its
+    /// nearest centroid and moves each centroid to the mean of every point it has ever been
+    /// assigned, by weighting the old centroid by its count. A centroid's learning rate therefore
+    /// decays as it sees more points, as in `minibatch_kmeans`.
+    ///
+    /// The first batch must have at least `k` points to choose centroids from. An empty batch
+    /// changes nothing.
+    #[cfg(feature = "thread_rng")]
+    pub fn partial_fit(&mut self, batch: Vec<P>) {
+        match self.seed {
+            Some(seed) => self.partial_fit_with_rng(batch, &mut StdRng::seed_from_u64(seed)),
+            None => self.partial_fit_with_rng(batch, &mut thread_rng())
+        }
+    }
+
+    /// `partial_fit`, drawing the starting centroids' randomness from `rng` and ignoring any `seed`
+    pub fn partial_fit_with_rng<R: Rng + ?Sized>(&mut self, batch: Vec<P>, rng: &mut R) {
+        if self.k == 0 || batch.is_empty() {
+            return;
+        }
+
+        if self.centroids.is_empty() {
+            let result = self.fit_with_rng(batch, rng);
+            self.counts = result.sizes();
+            self.centroids = result.clusters.into_iter().map(Cluster::into_centroids).collect();
+            return;
+        }
+
+        // Assign the whole batch against the same centroids before moving any of them
+        let mut members: Vec<Vec<P>> = vec![vec![]; self.k];
+        for point in batch {
+            let c = _nearest(&self.centroids, &point, &Euclidean);
+            members[c].push(point);
+        }
+
+        for (c, mut points) in members.into_iter().enumerate() {
+            if points.is_empty() {
+                continue;
+            }
+
+            let mut weights = vec![1.0; points.len() + 1];
+            weights[0] = self.counts[c] as f64;
+            self.counts[c] += points.len();
+            points.insert(0, self.centroids[c].clone());
+            self.centroids[c] = P::weighted_mean(&points, &weights);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "thread_rng"))]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::{kmeans_seeded, predict, total_inertia};
+    use crate::types::{Cluster, DataPoint};
+    use super::KMeans;
+
+    fn points() -> Vec<(f64, f64)> {
+        vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ]
+    }
+
+    #[test]
+    fn defaults_match_kmeans() {
+        for seed in 0..10 {
+            let result = KMeans::new(3).seed(seed).fit(points());
+            assert_eq!(result.clusters, kmeans_seeded(3, points(), seed));
+            assert!(result.converged);
+        }
+    }
+
+    #[test]
+    fn settings_apply() {
+        let config = KMeans::new(4).max_iter(1).seed(5);
+        let result = config.fit(points());
+        assert_eq!(result.iterations, 1);
+        assert_eq!(result, config.fit(points()));
+
+        for seed in 0..10 {
+            let once = KMeans::new(4).seed(seed).fit(points());
+            let best = KMeans::new(4).seed(seed).n_init(10).fit(points());
+            assert!(total_inertia(&best.clusters) <= total_inertia(&once.clusters));
+        }
+    }
+
+    #[test]
+    fn without_retained_points() {
+        for seed in 0..10 {
+            let kept = KMeans::new(3).seed(seed).fit(points());
+            let dropped = KMeans::new(3).seed(seed).with_retained_points(false).fit(points());
+            assert!(dropped.clusters.iter().all(|c| c.points.is_empty()));
+            assert_eq!(Cluster::centroids(&dropped.clusters), Cluster::centroids(&kept.clusters));
+            assert_eq!(dropped.labels, kept.labels);
+
+            assert_eq!(dropped.sizes(), kept.clusters.iter().map(Cluster::size).collect::<Vec<_>>());
+            assert_eq!(dropped.sizes().iter().sum::<usize>(), points().len());
+            assert!((dropped.inertia - total_inertia(&kept.clusters)).abs() < 1e-9);
+            assert_eq!(predict(&dropped.clusters, &(9.0, 9.0)), predict(&kept.clusters, &(9.0, 9.0)));
+        }
+
+        // Only the first batch is fitted, and the counts still come out right
+        let mut config = KMeans::new(2).seed(0).with_retained_points(false);
+        config.partial_fit(points());
+        assert_eq!(config.counts().iter().sum::<usize>(), points().len());
+    }
+
+    #[test]
+    fn caller_supplied_rng() {
+        for seed in 0..10 {
+            let config = KMeans::new(4).n_init(3);
+            let result = config.fit_with_rng(points(), &mut StdRng::seed_from_u64(seed));
+            assert_eq!(result, config.clone().seed(seed).fit(points()));
+            // The generator passed in wins over the configured seed
+            assert_eq!(result, config.seed(seed + 1).fit_with_rng(points(), &mut StdRng::seed_from_u64(seed)));
+        }
+    }
+
+    #[test]
+    fn partial_fit_in_halves() {
+        let mut points = points();
+        let full: Vec<(f64, f64)> = Cluster::centroids(&kmeans_seeded(2, points.clone(), 0));
+
+        for seed in 0..10 {
+            // Shuffled, so that each half looks like the whole
+            let mut rng = StdRng::seed_from_u64(seed);
+            rand::seq::SliceRandom::shuffle(&mut points[..], &mut rng);
+            let second_half = points.split_off(points.len() / 2);
+
+            let mut config = KMeans::new(2).seed(seed);
+            config.partial_fit(points.clone());
+            assert_eq!(config.counts().iter().sum::<usize>(), points.len());
+            config.partial_fit(second_half.clone());
+            assert_eq!(config.counts().iter().sum::<usize>(), points.len() + second_half.len());
+
+            for centroid in config.centroids() {
+                let nearest = full.iter().map(|c| c.dist(centroid)).fold(f64::INFINITY, f64::min);
+                assert!(nearest < 1.0, "{:?} is far from {:?}", centroid, full);
+            }
+            points.extend(second_half);
+        }
+
+        let mut config = KMeans::new(2);
+        config.partial_fit(Vec::<(f64, f64)>::new());
+        assert!(config.centroids().is_empty());
+    }
+}
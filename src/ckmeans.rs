@@ -0,0 +1,131 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+use crate::types::*;
+
+/// Clustering algorithm for plain numbers that finds the clustering with the lowest possible
+/// `total_inertia`, by the dynamic program of [Ckmeans.1d.dp].
+///
+/// On a line, every cluster of an optimal clustering is a run of consecutive values once they're
+/// sorted, so the best way to split the first `i` values into `m` clusters can be built up from the
+/// best ways to split fewer values into `m - 1`. Unlike `kmeans`, this never settles for a local
+/// optimum and involves no randomness, which makes it a good fit for natural breaks, like binning
+/// values for a choropleth map. It takes `O(k * n^2)` time and `O(k * n)` memory.
+///
+/// The clusters come out in increasing order, each holding its values sorted. There are never
+/// more clusters than values. NaN values can't be ordered, so they make the result meaningless.
+///
+/// [Ckmeans.1d.dp]: https://journal.r-project.org/archive/2011-2/RJournal_2011-2_Wang+Song.pdf
+pub fn kmeans_1d_optimal(k: usize, mut data: Vec<f64>) -> Vec<Cluster<f64>> {
+    data.sort_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+    let n = data.len();
+    let k = k.min(n);
+    if k == 0 {
+        return vec![];
+    }
+
+    // Running sums for the sum of squared deviations of any run of values in constant time,
+    // shifted by the median to keep the squares from losing precision
+    let shift = data[n / 2];
+    let mut sum = vec![0.0; n + 1];
+    let mut sum_sq = vec![0.0; n + 1];
+    for (i, x) in data.iter().enumerate() {
+        sum[i + 1] = sum[i] + (x - shift);
+        sum_sq[i + 1] = sum_sq[i] + (x - shift).powi(2);
+    }
+    let cost_of_run = |first: usize, last: usize| -> f64 {
+        let s = sum[last + 1] - sum[first];
+        let count = (last + 1 - first) as f64;
+        (sum_sq[last + 1] - sum_sq[first] - s * s / count).max(0.0)
+    };
+
+    // `cost[m * n + i]` is the least cost of splitting `data[..=i]` into `m + 1` clusters, and
+    // `first[m * n + i]` is where the last of those clusters starts
+    let mut cost = vec![0.0; k * n];
+    let mut first = vec![0; k * n];
+    for (i, c) in cost[..n].iter_mut().enumerate() {
+        *c = cost_of_run(0, i);
+    }
+    for m in 1..k {
+        for i in m..n {
+            let (mut best_cost, mut best_first) = (f64::INFINITY, i);
+            for j in m..=i {
+                let c = cost[(m - 1) * n + j - 1] + cost_of_run(j, i);
+                if c < best_cost {
+                    best_cost = c;
+                    best_first = j;
+                }
+            }
+            cost[m * n + i] = best_cost;
+            first[m * n + i] = best_first;
+        }
+    }
+
+    // Walk back from the last value to find where each cluster starts
+    let mut clusters = Vec::with_capacity(k);
+    let mut end = n;
+    for m in (0..k).rev() {
+        let start = first[m * n + end - 1];
+        let points = data[start..end].to_vec();
+        let centroid = f64::mean(&points).unwrap_or_default();
+        clusters.push(Cluster { centroid, points });
+        end = start;
+    }
+    clusters.reverse();
+    clusters
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use crate::{kmeans_from_centroids, total_inertia};
+    use crate::types::Cluster;
+    use super::kmeans_1d_optimal;
+
+    #[test]
+    fn beats_a_stuck_lloyd() {
+        let data = vec![101.0, 0.0, 12.0, 1.0, 100.0, 2.0, 10.0, 102.0, 11.0];
+
+        // Started badly, Lloyd's algorithm lumps the two lower groups together and splits the upper
+        let stuck = kmeans_from_centroids(vec![1.0, 101.0, 102.0], data.clone()).unwrap();
+        assert_eq!(total_inertia(&stuck), 154.5);
+
+        let optimal = kmeans_1d_optimal(3, data.clone());
+        assert_eq!(Cluster::centroids(&optimal), vec![1.0, 11.0, 101.0]);
+        assert_eq!(optimal[1].points, vec![10.0, 11.0, 12.0]);
+        assert_eq!(total_inertia(&optimal), 6.0);
+
+        assert_eq!(kmeans_1d_optimal(20, data.clone()).len(), data.len());
+        assert!(kmeans_1d_optimal(0, data).is_empty());
+        assert!(kmeans_1d_optimal(3, vec![]).is_empty());
+    }
+
+    #[test]
+    fn matches_every_split() {
+        let data = vec![4.5, -3.0, 7.25, 0.0, 1.5, 9.0, 2.0, 2.0, -1.0];
+        let mut sorted = data.clone();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        // Try every pair of places to cut the sorted values into three runs
+        let mut best = f64::INFINITY;
+        for a in 1..sorted.len() {
+            for b in (a + 1)..sorted.len() {
+                let runs = [&sorted[..a], &sorted[a..b], &sorted[b..]];
+                let clusters: Vec<Cluster<f64>> = runs.iter()
+                    .map(|run| {
+                        let mut cluster = Cluster { centroid: 0.0, points: run.to_vec() };
+                        cluster.recalculate_centroid();
+                        cluster
+                    })
+                    .collect();
+                best = best.min(total_inertia(&clusters));
+            }
+        }
+
+        assert!((total_inertia(&kmeans_1d_optimal(3, data)) - best).abs() < 1e-9);
+    }
+}
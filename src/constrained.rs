@@ -0,0 +1,261 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use rand::prelude::*;
+use crate::{_initialize_clusters, _nearest};
+use crate::metric::{Euclidean, Metric};
+use crate::types::*;
+
+/// The most rounds `constrained_kmeans` and `balanced_kmeans` run before settling for the
+/// clustering they have
+const CONSTRAINED_MAX_ITER: usize = 1000;
+
+/// Clustering algorithm using k-means++ where every cluster ends up with at least `min_size`
+/// points.
+///
+/// Each round, points are first assigned to their nearest centroid as usual. Then, while some
+/// cluster is short of `min_size`, a point is moved into it from a cluster with points to spare,
+/// picking whichever move adds the least to the total squared distance. This greedy balancing
+/// doesn't always find the best assignment that meets the minimum, but it always finds one.
+/// Centroids are recalculated as the mean of their points, and rounds continue until the
+/// assignment stops changing.
+///
+/// Fails if there are no points, if `k` is more than the number of points, or if there aren't
+/// enough points to give `k` clusters `min_size` each.
+pub fn constrained_kmeans<P: DataPoint>(k: usize, datapoints: Vec<P>, min_size: usize) -> Result<Vec<Cluster<P>>, KMeansError> {
+    _constrained(k, datapoints, min_size, &mut thread_rng())
+}
+
+fn _constrained<P, R>(k: usize, datapoints: Vec<P>, min_size: usize, rng: &mut R) -> Result<Vec<Cluster<P>>, KMeansError>
+    where P: DataPoint, R: Rng + ?Sized
+{
+    let n = datapoints.len();
+    if n == 0 {
+        return Err(KMeansError::NoData);
+    }
+    if k > n {
+        return Err(KMeansError::TooFewPoints { k, n });
+    }
+    if k.saturating_mul(min_size) > n {
+        return Err(KMeansError::MinSizeTooLarge { k, min_size, n });
+    }
+    if k == 0 {
+        return Ok(vec![]);
+    }
+
+    let centroids = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, &Euclidean, rng)
+        .into_iter()
+        .map(Cluster::into_centroids)
+        .collect();
+    Ok(_constrained_lloyd(centroids, datapoints, |centroids, datapoints| {
+        _min_size_assignment(centroids, datapoints, min_size)
+    }))
+}
+
+/// Clustering algorithm using k-means++ where the clusters all have the same number of points, or
+/// as near to it as the number of points allows: sizes differ by at most one.
+///
+/// Each round, every (point, centroid) pair is considered from nearest to farthest, and the point
+/// is assigned to the centroid if it hasn't been assigned yet and the cluster isn't full. Only
+/// `n % k` of the clusters get the extra point. This greedy assignment keeps points with their
+/// nearest centroid where it can, but isn't guaranteed to find the least total distance.
+/// Centroids are recalculated as the mean of their points, and rounds continue until the
+/// assignment stops changing.
+///
+/// `k` shouldn't be more than the number of points.
+pub fn balanced_kmeans<P: DataPoint>(k: usize, datapoints: Vec<P>) -> Vec<Cluster<P>> {
+    _balanced(k, datapoints, &mut thread_rng())
+}
+
+fn _balanced<P, R>(k: usize, datapoints: Vec<P>, rng: &mut R) -> Vec<Cluster<P>>
+    where P: DataPoint, R: Rng + ?Sized
+{
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    let centroids = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, &Euclidean, rng)
+        .into_iter()
+        .map(Cluster::into_centroids)
+        .collect();
+    _constrained_lloyd(centroids, datapoints, _equal_size_assignment)
+}
+
+/// `_lloyd`, assigning points to clusters with `assign` instead of to their nearest centroid
+fn _constrained_lloyd<P, F>(mut centroids: Vec<P>, datapoints: Vec<P>, assign: F) -> Vec<Cluster<P>>
+    where P: DataPoint, F: Fn(&[P], &[P]) -> Vec<usize>
+{
+    let mut labels = assign(&centroids, &datapoints);
+    for _ in 0..CONSTRAINED_MAX_ITER {
+        for (c, centroid) in centroids.iter_mut().enumerate() {
+            let members: Vec<P> = datapoints.iter().zip(labels.iter())
+                .filter(|(_, label)| **label == c)
+                .map(|(p, _)| p.clone())
+                .collect();
+            if let Some(mean) = P::mean(&members) {
+                *centroid = mean;
+            }
+        }
+
+        let updated = assign(&centroids, &datapoints);
+        if updated == labels {
+            break;
+        }
+        labels = updated;
+    }
+
+    let mut clusters: Vec<Cluster<P>> = centroids.iter().map(Cluster::new).collect();
+    for (point, label) in datapoints.into_iter().zip(labels) {
+        clusters[label].points.push(point);
+    }
+    clusters
+}
+
+/// Assigns each point to its nearest centroid, then fills up the clusters with fewer than
+/// `min_size` points, one cheapest move at a time. There must be at least `min_size` points for
+/// each centroid.
+fn _min_size_assignment<P: DataPoint>(centroids: &[P], datapoints: &[P], min_size: usize) -> Vec<usize> {
+    let distances: Vec<Vec<f64>> = datapoints.iter()
+        .map(|p| centroids.iter().map(|c| Euclidean.distance_sq(p, c)).collect())
+        .collect();
+    let mut labels: Vec<usize> = datapoints.iter().map(|p| _nearest(centroids, p, &Euclidean)).collect();
+    let mut sizes = vec![0; centroids.len()];
+    for label in labels.iter() {
+        sizes[*label] += 1;
+    }
+
+    // While any cluster is short, some other cluster has more than enough, so there's always a move
+    loop {
+        let mut cheapest: Option<(usize, usize, f64)> = None;
+        for (i, row) in distances.iter().enumerate() {
+            let from = labels[i];
+            if sizes[from] <= min_size {
+                continue;
+            }
+
+            for (to, distance) in row.iter().enumerate() {
+                let cost = distance - row[from];
+                if sizes[to] < min_size && cheapest.is_none_or(|(_, _, least)| cost < least) {
+                    cheapest = Some((i, to, cost));
+                }
+            }
+        }
+
+        match cheapest {
+            Some((i, to, _)) => {
+                sizes[labels[i]] -= 1;
+                sizes[to] += 1;
+                labels[i] = to;
+            }
+            None => break
+        }
+    }
+    labels
+}
+
+/// Assigns points to centroids nearest pair first, without letting any cluster get more than its
+/// share of the points
+fn _equal_size_assignment<P: DataPoint>(centroids: &[P], datapoints: &[P]) -> Vec<usize> {
+    let k = centroids.len();
+    let mut pairs: Vec<(f64, usize, usize)> = Vec::with_capacity(datapoints.len() * k);
+    for (i, p) in datapoints.iter().enumerate() {
+        for (c, centroid) in centroids.iter().enumerate() {
+            pairs.push((Euclidean.distance_sq(p, centroid), i, c));
+        }
+    }
+    pairs.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(core::cmp::Ordering::Equal));
+
+    // Every cluster gets `share` points, and the first `extra` to fill up get one more
+    let share = datapoints.len() / k;
+    let mut extra = datapoints.len() % k;
+    let mut sizes = vec![0; k];
+    let mut labels = vec![None; datapoints.len()];
+    for (_, i, c) in pairs {
+        if labels[i].is_some() || sizes[c] > share || (sizes[c] == share && extra == 0) {
+            continue;
+        }
+
+        if sizes[c] == share {
+            extra -= 1;
+        }
+        sizes[c] += 1;
+        labels[i] = Some(c);
+    }
+    labels.into_iter().map(|label| label.expect("every point has room in some cluster")).collect()
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::kmeans_with_rng;
+    use crate::types::{Cluster, KMeansError};
+    use super::{_balanced, _constrained, balanced_kmeans, constrained_kmeans};
+
+    #[test]
+    fn meets_minimum_size() {
+        let mut points = vec![];
+        for i in 0..20 {
+            points.push((i as f64 % 5.0, (i / 5) as f64));
+            points.push((20.0 + i as f64 % 5.0, (i / 5) as f64));
+        }
+        points.push((100.0, 100.0));
+
+        for seed in 0..10 {
+            // The far-off point gets a cluster all to itself
+            let plain = kmeans_with_rng(3, points.clone(), &mut StdRng::seed_from_u64(seed));
+            assert_eq!(plain.iter().map(Cluster::size).min(), Some(1));
+
+            let clusters = _constrained(3, points.clone(), 8, &mut StdRng::seed_from_u64(seed)).unwrap();
+            assert_eq!(clusters.len(), 3);
+            assert!(clusters.iter().all(|c| c.size() >= 8));
+            assert_eq!(clusters.iter().map(Cluster::size).sum::<usize>(), points.len());
+            for cluster in clusters.iter() {
+                assert_eq!(Some(cluster.centroid), crate::types::DataPoint::mean(&cluster.points));
+            }
+        }
+    }
+
+    #[test]
+    fn checks_arguments() {
+        let points = vec![(0f64, 0f64), (1.0, 0.0), (2.0, 0.0), (3.0, 0.0), (4.0, 0.0)];
+        assert_eq!(constrained_kmeans(2, points.clone(), 3), Err(KMeansError::MinSizeTooLarge { k: 2, min_size: 3, n: 5 }));
+        assert_eq!(constrained_kmeans(6, points.clone(), 0), Err(KMeansError::TooFewPoints { k: 6, n: 5 }));
+        assert_eq!(constrained_kmeans(2, Vec::<(f64, f64)>::new(), 0), Err(KMeansError::NoData));
+        assert_eq!(constrained_kmeans(0, points.clone(), 3), Ok(vec![]));
+
+        let clusters = constrained_kmeans(5, points, 1).unwrap();
+        assert!(clusters.iter().all(|c| c.size() == 1));
+    }
+
+    #[test]
+    fn equal_sizes() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ];
+
+        for seed in 0..10 {
+            for k in 2..=5 {
+                let clusters = _balanced(k, points.clone(), &mut StdRng::seed_from_u64(seed));
+                let sizes: Vec<usize> = clusters.iter().map(Cluster::size).collect();
+                assert_eq!(sizes.len(), k);
+                assert_eq!(sizes.iter().sum::<usize>(), points.len());
+                assert!(sizes.iter().max().unwrap() - sizes.iter().min().unwrap() <= 1);
+            }
+        }
+
+        // The lower-left blob is short of half, so it takes a point or two from the other one
+        let mut clusters = balanced_kmeans(2, points.clone());
+        clusters.sort_by(|a, b| a.centroid.partial_cmp(&b.centroid).unwrap());
+        assert!(clusters[0].size() == 16 || clusters[0].size() == 17);
+        assert!(points[..15].iter().all(|p| clusters[0].points.contains(p)));
+        assert!(balanced_kmeans(2, Vec::<(f64, f64)>::new()).is_empty());
+    }
+}
@@ -0,0 +1,218 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use rand::prelude::*;
+use core::cmp::Ordering;
+use crate::{_fill_empty_clusters, _initialize_clusters, _intermediate_inertia, CycleCheck, StoppingCriteria};
+use crate::metric::Euclidean;
+use crate::types::*;
+
+/// Clustering algorithm using k-means++, finding each point's nearest centroid by dot products.
+///
+/// The squared distance between `a` and `b` is `|a|² + |b|² - 2·a·b`. Every point's squared norm
+/// is worked out once up front, and every centroid's once a round, so measuring a point against
+/// each centroid takes one dot product rather than a difference and a square per coordinate. That
+/// pays off for points with many dimensions, like long `Vec<f64>`s.
+///
+/// This gives exactly the same clustering as `kmeans` from the same starting centroids. The dot
+/// product shortcut can round differently from `DataPoint::dist_sq`, so whenever it puts more than
+/// one centroid within rounding error of the nearest, those are measured again the usual way.
+///
+/// The points' `dist_sq` has to be the squared straight-line distance between their coordinates,
+/// as it is for tuples, arrays and `Vec<f64>`.
+pub fn kmeans_dense<P: DataPoint + Coordinates>(k: usize, datapoints: Vec<P>) -> Vec<Cluster<P>> {
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    let clusters = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, &Euclidean, &mut thread_rng());
+    _dense(clusters.into_iter().map(Cluster::into_centroids).collect(), &datapoints, StoppingCriteria::default()).clusters
+}
+
+/// The squared norm of `p`
+fn norm_sq<P: Coordinates>(p: &P) -> f64 {
+    (0..p.dimensions()).map(|i| p.coordinate(i).powi(2)).sum()
+}
+
+/// The dot product of `a` and `b`
+fn dot<P: Coordinates>(a: &P, b: &P) -> f64 {
+    (0..a.dimensions()).map(|i| a.coordinate(i) * b.coordinate(i)).sum()
+}
+
+/// The same loop as `_lloyd`, but assigning points with `nearest`
+fn _dense<P: DataPoint + Coordinates>(centroids: Vec<P>, datapoints: &[P], stop: StoppingCriteria) -> KMeansResult<P> {
+    let mut clusters: Vec<IntermediateCluster<P>> = centroids.into_iter()
+        .map(|centroid| IntermediateCluster { centroid, point_indices: vec![] })
+        .collect();
+    let norms: Vec<f64> = datapoints.iter().map(norm_sq).collect();
+    // Space for each point's estimated squared distance to each centroid
+    let mut estimates = vec![0.0; clusters.len()];
+
+    let mut centroid_norms: Vec<f64> = clusters.iter().map(|c| norm_sq(&c.centroid)).collect();
+    let mut labels: Vec<usize> = Vec::with_capacity(datapoints.len());
+    for (i, point) in datapoints.iter().enumerate() {
+        let label = nearest(point, norms[i], &clusters, &centroid_norms, &mut estimates);
+        clusters[label].point_indices.push(i);
+        labels.push(label);
+    }
+    _fill_empty_clusters(&mut clusters, &mut labels, datapoints, &Euclidean);
+
+    let mut iterations = 0;
+    let mut converged = false;
+    let mut cycle = CycleCheck::new(&labels);
+    while iterations < stop.max_iter && !stop.past_deadline() {
+        // Recalculate the means based on the points in the cluster, keeping track of how far the
+        // farthest-moving one went
+        let mut shift = 0.0f64;
+        for (cluster, centroid_norm) in clusters.iter_mut().zip(centroid_norms.iter_mut()) {
+            let old_centroid = cluster.recalculate_centroid(datapoints);
+            shift = shift.max(old_centroid.dist(&cluster.centroid));
+            cluster.point_indices.clear();
+            *centroid_norm = norm_sq(&cluster.centroid);
+        }
+
+        // Cluster again, noting whether anything moved
+        let previous = labels.clone();
+        for (i, point) in datapoints.iter().enumerate() {
+            let label = nearest(point, norms[i], &clusters, &centroid_norms, &mut estimates);
+            labels[i] = label;
+            clusters[label].point_indices.push(i);
+        }
+        // A moved centroid's norm is out of date, but it's recalculated before it's next needed.
+        // A refilled cluster can take back the very point it had, which isn't a change.
+        _fill_empty_clusters(&mut clusters, &mut labels, datapoints, &Euclidean);
+        iterations += 1;
+
+        if !cycle.lapping() && (labels == previous || shift < stop.tol) {
+            converged = true;
+            break;
+        }
+        if cycle.check(&labels, || _intermediate_inertia(&clusters, datapoints, &Euclidean), || (clusters.clone(), labels.clone())) {
+            break;
+        }
+    }
+    // Going around in a cycle, finish on its best state
+    if let Some(best) = cycle.into_best() {
+        (clusters, labels) = best;
+    }
+
+    let inertia = _intermediate_inertia(&clusters, datapoints, &Euclidean);
+    let clusters = clusters.iter()
+        .map(|c| Cluster::from_intermediate(c, datapoints))
+        .collect();
+    KMeansResult { clusters, labels, iterations, converged, inertia, inertia_history: vec![] }
+}
+
+/// The index of the centroid nearest `point`, whose squared norm is `norm`, decided the same way
+/// as `_nearest`. `estimates` is scratch space with room for every cluster.
+fn nearest<P: DataPoint + Coordinates>(point: &P, norm: f64, clusters: &[IntermediateCluster<P>], centroid_norms: &[f64], estimates: &mut [f64]) -> usize {
+    // Each sum of `n` products is off by at most about `n` units in the last place of the sum of
+    // their magnitudes, and those magnitudes are all bounded by the two norms. This is a
+    // comfortable overestimate of the error in both the estimate and `dist_sq`.
+    let slack = 4.0 * (point.dimensions() + 2) as f64 * f64::EPSILON;
+    let error = |c: usize| slack * (norm + centroid_norms[c]);
+
+    let mut threshold = f64::INFINITY;
+    for (c, (cluster, estimate)) in clusters.iter().zip(estimates.iter_mut()).enumerate() {
+        *estimate = norm + centroid_norms[c] - 2.0 * dot(point, &cluster.centroid);
+        threshold = threshold.min(*estimate + error(c));
+    }
+
+    // Only centroids that could be the nearest, given the rounding, are measured exactly
+    let mut closest_cluster = 0;
+    let mut closest_distance = f64::INFINITY;
+    for (c, cluster) in clusters.iter().enumerate() {
+        if estimates[c] - error(c) > threshold {
+            continue;
+        }
+
+        let distance = point.dist_sq(&cluster.centroid);
+        let tied = distance == closest_distance && cluster.centroid.tie_order(&clusters[closest_cluster].centroid) == Ordering::Less;
+        if distance < closest_distance || tied {
+            closest_cluster = c;
+            closest_distance = distance;
+        }
+    }
+    closest_cluster
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use crate::{_initialize_clusters, _lloyd, StoppingCriteria};
+    use crate::metric::Euclidean;
+    use crate::types::{Cluster, Coordinates, DataPoint, InitMethod};
+    use super::{_dense, kmeans_dense};
+
+    fn assert_same_as_lloyd<P: DataPoint + Coordinates + core::fmt::Debug>(k: usize, points: Vec<P>) {
+        for seed in 0..10 {
+            let start = _initialize_clusters(k, &points, InitMethod::KMeansPlusPlus, &Euclidean, &mut StdRng::seed_from_u64(seed));
+            let start: Vec<P> = start.into_iter().map(Cluster::into_centroids).collect();
+
+            let lloyd = _lloyd(start.clone(), &points, StoppingCriteria::default(), &Euclidean);
+            let dense = _dense(start, &points, StoppingCriteria::default());
+
+            assert_eq!(dense, lloyd);
+        }
+    }
+
+    #[test]
+    fn matches_lloyd() {
+        let points = vec![
+            (1u8, 2u8), (1, 3), (2, 2), (2, 3), (2, 4),
+            (3, 1), (3, 2), (3, 3), (3, 4), (4, 1), (4, 2), (4, 3),
+            (4, 4), (5, 2), (5, 3),
+            (6, 5), (6, 6), (6, 7), (7, 5), (7, 6), (7, 7), (7, 8),
+            (8, 4), (8, 5), (8, 6), (8, 7), (8, 8), (9, 5), (9, 6),
+            (9, 7), (9, 8), (10, 6), (10, 7)
+        ];
+        // Lots of points exactly halfway between centroids
+        assert_same_as_lloyd(2, points.clone());
+        assert_same_as_lloyd(4, points);
+
+        // Far from the origin, where the norms swamp the distances between points
+        let mut rng = StdRng::seed_from_u64(0);
+        let points: Vec<Vec<f64>> = (0..200)
+            .map(|i| (0..64).map(|_| 1e6 + (i % 4) as f64 + rng.gen_range(-0.6..0.6)).collect())
+            .collect();
+        assert_same_as_lloyd(4, points.clone());
+        assert_same_as_lloyd(7, points);
+
+        // The duplicated points are split between two identical centroids the same way every round
+        assert_same_as_lloyd(4, vec![(-1f64, -1f64), (-4.0, -3.0), (-4.0, -3.0), (-2.0, 4.0)]);
+
+        assert!(kmeans_dense(3, Vec::<(f64, f64)>::new()).is_empty());
+    }
+
+    #[test]
+    fn breaks_oscillation() {
+        // The integer points from `kmeans`'s own test, which go around in a cycle
+        let points = vec![(-1i8, 3i8), (3, -4), (0, 2), (-1, 1), (-1, 2)];
+        let start = vec![(-1, 3), (-1, 1), (-1, 1)];
+        let lloyd = _lloyd(start.clone(), &points, StoppingCriteria::default(), &Euclidean);
+        let dense = _dense(start, &points, StoppingCriteria::default());
+        assert!(!dense.converged);
+        assert_eq!(dense.inertia, 2.0);
+        assert_eq!(dense, lloyd);
+
+        assert_same_as_lloyd(3, points);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn matches_lloyd_on_file() {
+        let file = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/test_data.txt")).unwrap();
+        let data: Vec<[f64; 3]> = file.trim().lines()
+            .map(|line| {
+                let coords: Vec<f64> = line.split_whitespace().map(|s| s.parse().unwrap()).collect();
+                [coords[0], coords[1], coords[2]]
+            })
+            .collect();
+
+        assert_same_as_lloyd(2, data.clone());
+        assert_same_as_lloyd(8, data);
+    }
+}
@@ -0,0 +1,262 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use rand::prelude::*;
+use core::cmp::Ordering;
+use crate::{_fill_empty_clusters, _initialize_clusters, _intermediate_inertia, CycleCheck, StoppingCriteria};
+use crate::metric::Euclidean;
+use crate::types::*;
+
+/// Clustering algorithm using k-means++ and [Elkan's] triangle-inequality acceleration.
+///
+/// This gives exactly the same clustering as `kmeans` from the same starting centroids, but keeps
+/// bounds on each point's distance to each centroid so that most distance calculations can be
+/// skipped once the centroids stop moving much. The price is memory: a lower bound for every
+/// point/centroid pair (`n * k` floats), an upper bound per point, and the `k * k` distances
+/// between centroids.
+///
+/// [Elkan's]: https://www.aaai.org/Papers/ICML/2003/ICML03-022.pdf
+pub fn kmeans_elkan<P: DataPoint>(k: usize, datapoints: Vec<P>) -> Vec<Cluster<P>> {
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    let clusters = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, &Euclidean, &mut thread_rng());
+    _elkan(clusters.into_iter().map(Cluster::into_centroids).collect(), &datapoints, StoppingCriteria::default()).clusters
+}
+
+/// Whether a point exactly as close to the centroids of clusters `a` and `b` should go to `a`, the
+/// same way `_nearest` decides it
+fn wins_tie<P: DataPoint>(clusters: &[IntermediateCluster<P>], a: usize, b: usize) -> bool {
+    match clusters[a].centroid.tie_order(&clusters[b].centroid) {
+        Ordering::Less => true,
+        Ordering::Equal => a < b,
+        Ordering::Greater => false
+    }
+}
+
+/// The same loop as `_lloyd`, but skipping the distance calculations that the triangle inequality
+/// shows can't change a point's assignment
+fn _elkan<P: DataPoint>(centroids: Vec<P>, datapoints: &[P], stop: StoppingCriteria) -> KMeansResult<P> {
+    let k = centroids.len();
+    let mut clusters: Vec<IntermediateCluster<P>> = centroids.into_iter()
+        .map(|centroid| IntermediateCluster { centroid, point_indices: vec![] })
+        .collect();
+
+    // `lower[i * k + c]` never exceeds the distance from point `i` to centroid `c`, and `upper[i]`
+    // is never less than the distance from point `i` to the centroid of its own cluster
+    let mut lower = vec![0.0; datapoints.len() * k];
+    let mut upper = vec![0.0; datapoints.len()];
+
+    // The first assignment has to measure everything, which makes all the bounds exact
+    let mut labels = Vec::with_capacity(datapoints.len());
+    for (i, point) in datapoints.iter().enumerate() {
+        let bounds = &mut lower[i * k..(i + 1) * k];
+        let mut label = 0;
+        for (c, cluster) in clusters.iter().enumerate() {
+            bounds[c] = point.dist(&cluster.centroid);
+            if bounds[c] < bounds[label] || (bounds[c] == bounds[label] && wins_tie(&clusters, c, label)) {
+                label = c;
+            }
+        }
+        upper[i] = bounds[label];
+        clusters[label].point_indices.push(i);
+        labels.push(label);
+    }
+    fill_empty_clusters(&mut clusters, &mut labels, datapoints, &mut lower, &mut upper);
+
+    let mut shifts = vec![0.0; k];
+    let mut centroid_distances = vec![0.0; k * k];
+    let mut iterations = 0;
+    let mut converged = false;
+    let mut cycle = CycleCheck::new(&labels);
+    while iterations < stop.max_iter && !stop.past_deadline() {
+        // Recalculate the means based on the points in the cluster
+        for (c, cluster) in clusters.iter_mut().enumerate() {
+            let old_centroid = cluster.recalculate_centroid(datapoints);
+            shifts[c] = old_centroid.dist(&cluster.centroid);
+            cluster.point_indices.clear();
+        }
+        let shift = shifts.iter().cloned().fold(0.0, f64::max);
+
+        // A centroid moving by `d` can bring it at most `d` closer to, or farther from, any point
+        for (i, label) in labels.iter().enumerate() {
+            upper[i] += shifts[*label];
+            for c in 0..k {
+                lower[i * k + c] = f64::max(lower[i * k + c] - shifts[c], 0.0);
+            }
+        }
+
+        for a in 0..k {
+            for b in 0..k {
+                centroid_distances[a * k + b] = clusters[a].centroid.dist(&clusters[b].centroid);
+            }
+        }
+
+        // Cluster again, noting whether anything moved
+        let previous = labels.clone();
+        for (i, point) in datapoints.iter().enumerate() {
+            let mut label = labels[i];
+            let mut upper_is_exact = false;
+            for c in 0..k {
+                // Centroid `c` can only be closer than (or as close as) the current one if it's
+                // within the upper bound, and not so far from the current centroid that the
+                // triangle inequality rules it out. The comparisons are strict so that ties are
+                // still measured and broken the same way `_nearest` breaks them.
+                let (lower_bound, half_gap) = (lower[i * k + c], 0.5 * centroid_distances[label * k + c]);
+                let ruled_out = |upper: f64| upper < lower_bound || upper < half_gap;
+                if c == label || ruled_out(upper[i]) {
+                    continue;
+                }
+
+                if !upper_is_exact {
+                    upper[i] = point.dist(&clusters[label].centroid);
+                    lower[i * k + label] = upper[i];
+                    upper_is_exact = true;
+                    if ruled_out(upper[i]) {
+                        continue;
+                    }
+                }
+
+                let distance = point.dist(&clusters[c].centroid);
+                lower[i * k + c] = distance;
+                if distance < upper[i] || (distance == upper[i] && wins_tie(&clusters, c, label)) {
+                    label = c;
+                    upper[i] = distance;
+                }
+            }
+
+            labels[i] = label;
+            clusters[label].point_indices.push(i);
+        }
+        // A refilled cluster can take back the very point it had, which isn't a change
+        fill_empty_clusters(&mut clusters, &mut labels, datapoints, &mut lower, &mut upper);
+        iterations += 1;
+
+        if !cycle.lapping() && (labels == previous || shift < stop.tol) {
+            converged = true;
+            break;
+        }
+        if cycle.check(&labels, || _intermediate_inertia(&clusters, datapoints, &Euclidean), || (clusters.clone(), labels.clone())) {
+            break;
+        }
+    }
+    // Going around in a cycle, finish on its best state
+    if let Some(best) = cycle.into_best() {
+        (clusters, labels) = best;
+    }
+
+    let inertia = _intermediate_inertia(&clusters, datapoints, &Euclidean);
+    let clusters = clusters.iter()
+        .map(|c| Cluster::from_intermediate(c, datapoints))
+        .collect();
+    KMeansResult { clusters, labels, iterations, converged, inertia, inertia_history: vec![] }
+}
+
+/// `_fill_empty_clusters`, also fixing up the bounds for the centroids and points that moved
+fn fill_empty_clusters<P: DataPoint>(clusters: &mut [IntermediateCluster<P>], labels: &mut [usize], datapoints: &[P], lower: &mut [f64], upper: &mut [f64]) {
+    let k = clusters.len();
+    let moved = _fill_empty_clusters(clusters, labels, datapoints, &Euclidean);
+    for i in moved.iter() {
+        // Nothing is known about how far anything is from the moved centroid any more, except the
+        // point it was moved onto
+        let label = labels[*i];
+        for bound in lower.iter_mut().skip(label).step_by(k) {
+            *bound = 0.0;
+        }
+        upper[*i] = datapoints[*i].dist(&clusters[label].centroid);
+        lower[*i * k + label] = upper[*i];
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::{_initialize_clusters, _lloyd, StoppingCriteria};
+    use crate::metric::Euclidean;
+    use crate::types::{Cluster, DataPoint, InitMethod};
+    use super::_elkan;
+
+    fn assert_same_as_lloyd<P: DataPoint + core::fmt::Debug>(k: usize, points: Vec<P>) {
+        for seed in 0..10 {
+            let start = _initialize_clusters(k, &points, InitMethod::KMeansPlusPlus, &Euclidean, &mut StdRng::seed_from_u64(seed));
+            let start: Vec<P> = start.into_iter().map(Cluster::into_centroids).collect();
+
+            let lloyd = _lloyd(start.clone(), &points, StoppingCriteria::default(), &Euclidean);
+            let elkan = _elkan(start, &points, StoppingCriteria::default());
+
+            assert_eq!(elkan, lloyd);
+        }
+    }
+
+    #[test]
+    fn matches_lloyd() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ];
+        assert_same_as_lloyd(2, points.clone());
+        assert_same_as_lloyd(5, points);
+
+        let points = vec![
+            (1u8, 2u8), (1, 3), (2, 2), (2, 3), (2, 4),
+            (3, 1), (3, 2), (3, 3), (3, 4), (4, 1), (4, 2), (4, 3),
+            (4, 4), (5, 2), (5, 3),
+            (6, 5), (6, 6), (6, 7), (7, 5), (7, 6), (7, 7), (7, 8),
+            (8, 4), (8, 5), (8, 6), (8, 7), (8, 8), (9, 5), (9, 6),
+            (9, 7), (9, 8), (10, 6), (10, 7)
+        ];
+        assert_same_as_lloyd(2, points.clone());
+        assert_same_as_lloyd(4, points);
+
+        // Nothing is nearest the far-off centroids at first
+        let points = vec![(1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (8.0, 4.0), (8.0, 5.0)];
+        let start = vec![(5.0, 3.0), (100.0, 100.0), (-100.0, 0.0)];
+        let lloyd = _lloyd(start.clone(), &points, StoppingCriteria::default(), &Euclidean);
+        let elkan = _elkan(start, &points, StoppingCriteria::default());
+        assert_eq!(elkan, lloyd);
+
+        // The duplicated points are split between two identical centroids the same way every round
+        assert_same_as_lloyd(4, vec![(-1f64, -1f64), (-4.0, -3.0), (-4.0, -3.0), (-2.0, 4.0)]);
+    }
+
+    #[test]
+    fn breaks_oscillation() {
+        // The integer points from `kmeans`'s own test, which go around in a cycle
+        let points = vec![(-1i8, 3i8), (3, -4), (0, 2), (-1, 1), (-1, 2)];
+        let start = vec![(-1, 3), (-1, 1), (-1, 1)];
+        let lloyd = _lloyd(start.clone(), &points, StoppingCriteria::default(), &Euclidean);
+        let elkan = _elkan(start, &points, StoppingCriteria::default());
+        assert!(!elkan.converged);
+        assert_eq!(elkan.inertia, 2.0);
+        assert_eq!(elkan, lloyd);
+
+        assert_same_as_lloyd(3, points);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn matches_lloyd_on_file() {
+        let file = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/test_data.txt")).unwrap();
+        let data: Vec<(f64, f64, f64)> = file.trim().split('\n')
+            .map(|line| {
+                let strings: Vec<&str> = line.trim().split(' ').collect();
+                (
+                    strings[0].parse::<f64>().unwrap(),
+                    strings[1].parse::<f64>().unwrap(),
+                    strings[2].parse::<f64>().unwrap(),
+                )
+            })
+            .collect();
+
+        assert_same_as_lloyd(2, data.clone());
+        assert_same_as_lloyd(8, data);
+    }
+}
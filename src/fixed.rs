@@ -0,0 +1,133 @@
+use rand::prelude::*;
+use crate::{_nearest, select_point, shortest_center_distance, CycleCheck};
+use crate::metric::Euclidean;
+use crate::types::*;
+
+/// Clustering algorithm using k-means++ that never allocates, for when there's no heap. The number
+/// of clusters `K` and of points `N` are both fixed at compile time, and all the working space
+/// lives on the stack in arrays and `heapless::Vec`s of those sizes.
+///
+/// This picks the same starting centroids as `kmeans_with_rng` given the same `rng`, and then
+/// recenters and reassigns until no point changes cluster, or, for a clustering going around in a
+/// cycle, stops on the state along it with the lowest inertia. The only difference is that a
+/// cluster left without points keeps its centroid rather than taking a point from another cluster.
+///
+/// Every cluster has room for all `N` points, so this needs `K * N` points' worth of stack.
+/// Panics if `K` is more than `N`.
+pub fn kmeans_fixed<P, R, const K: usize, const N: usize>(points: &[P; N], rng: &mut R) -> [FixedCluster<P, N>; K]
+    where P: DataPoint, R: Rng + ?Sized
+{
+    assert!(K <= N, "can't make {} clusters from {} points", K, N);
+    if K == 0 {
+        return core::array::from_fn(|_| unreachable!());
+    }
+
+    _fixed_lloyd(_fixed_kmeans_plus_plus(points, rng), points)
+}
+
+/// The loop of `kmeans_fixed`, from the given starting `centroids`
+fn _fixed_lloyd<P: DataPoint, const K: usize, const N: usize>(mut centroids: [P; K], points: &[P; N]) -> [FixedCluster<P, N>; K] {
+    let mut labels: [usize; N] = core::array::from_fn(|i| _nearest(centroids.iter(), &points[i], &Euclidean));
+    let mut cycle = CycleCheck::new(&labels);
+    loop {
+        for (c, centroid) in centroids.iter_mut().enumerate() {
+            let members: heapless::Vec<P, N> = points.iter().zip(labels.iter())
+                .filter(|(_, label)| **label == c)
+                .map(|(p, _)| p.clone())
+                .collect();
+            if let Some(mean) = P::mean(&members) {
+                *centroid = mean;
+            }
+        }
+
+        let mut changed = false;
+        for (label, point) in labels.iter_mut().zip(points.iter()) {
+            let new_label = _nearest(centroids.iter(), point, &Euclidean);
+            changed |= new_label != *label;
+            *label = new_label;
+        }
+        if !cycle.lapping() && !changed {
+            break;
+        }
+        let inertia = || points.iter().zip(labels.iter()).map(|(p, label)| p.dist_sq(&centroids[*label])).sum();
+        if cycle.check(&labels, inertia, || (centroids.clone(), labels)) {
+            break;
+        }
+    }
+    if let Some(best) = cycle.into_best() {
+        (centroids, labels) = best;
+    }
+
+    let mut clusters = centroids.map(|centroid| FixedCluster { centroid, points: heapless::Vec::new() });
+    for (point, label) in points.iter().zip(labels) {
+        // Every cluster has room for every point
+        clusters[label].points.push(point.clone()).unwrap_or_else(|_| unreachable!());
+    }
+    clusters
+}
+
+/// `_kmeans_plus_plus`, keeping the candidates and centroids in `heapless::Vec`s. There must be at
+/// least one point.
+fn _fixed_kmeans_plus_plus<P, R, const K: usize, const N: usize>(points: &[P; N], rng: &mut R) -> [P; K]
+    where P: DataPoint, R: Rng + ?Sized
+{
+    let mut remaining: heapless::Vec<&P, N> = points.iter().collect();
+    let mut centers: heapless::Vec<&P, K> = heapless::Vec::new();
+    // First centroid is selected with a uniform distribution
+    let first_point = remaining.remove(rng.gen_range(0..remaining.len()));
+    centers.push(first_point).unwrap_or_else(|_| unreachable!());
+
+    // Keep selecting unique points until we have `K` centroids
+    while centers.len() < K {
+        let distribution: heapless::Vec<f64, N> = remaining.iter()
+            .map(|p| shortest_center_distance(&centers, p, &Euclidean))
+            .collect();
+        let point = remaining.remove(select_point(&distribution, rng));
+        centers.push(point).unwrap_or_else(|_| unreachable!());
+    }
+
+    core::array::from_fn(|c| centers[c].clone())
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::kmeans_with_rng;
+    use super::{_fixed_lloyd, kmeans_fixed};
+
+    #[test]
+    fn matches_kmeans() {
+        let points = [
+            (1f64, 1f64), (1.5, 2.0), (3.0, 4.0), (5.0, 7.0), (3.5, 5.0), (4.5, 5.0), (3.5, 4.5),
+            (9.0, 1.0), (9.5, 1.5), (8.5, 0.5), (10.0, 2.0), (0.0, 9.0), (0.5, 8.5), (1.0, 10.0)
+        ];
+
+        for seed in 0..10 {
+            let fixed: [_; 3] = kmeans_fixed(&points, &mut StdRng::seed_from_u64(seed));
+            let clusters = kmeans_with_rng(3, points.to_vec(), &mut StdRng::seed_from_u64(seed));
+            for (fixed, cluster) in fixed.iter().zip(clusters.iter()) {
+                assert_eq!(fixed.centroid, cluster.centroid);
+                assert_eq!(&fixed.points[..], &cluster.points[..]);
+            }
+            assert_eq!(fixed.iter().map(|c| c.size()).sum::<usize>(), points.len());
+        }
+
+        let none: [_; 0] = kmeans_fixed(&points, &mut StdRng::seed_from_u64(0));
+        assert!(none.is_empty());
+    }
+
+    #[test]
+    fn breaks_oscillation() {
+        // Centroids rounded toward zero keep trading points back and forth from here
+        let points = [
+            (-2i8, 1i8), (-2, -1), (-1, 0), (-2, 0), (-2, 2), (-2, -2), (-2, 0), (-2, 1), (-2, 0), (1, 1)
+        ];
+        let fixed = _fixed_lloyd([(-2, 0), (-2, -2), (1, 1)], &points);
+        assert_eq!(fixed.iter().map(|c| c.size()).sum::<usize>(), points.len());
+
+        for seed in 0..10 {
+            let fixed: [_; 3] = kmeans_fixed(&points, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(fixed.iter().map(|c| c.size()).sum::<usize>(), points.len());
+        }
+    }
+}
@@ -0,0 +1,153 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use rand::prelude::*;
+use crate::_initialize_clusters;
+use crate::metric::Euclidean;
+use crate::types::*;
+
+/// The most rounds `fuzzy_cmeans` runs before giving up on the memberships settling
+const FUZZY_MAX_ITER: usize = 1000;
+
+/// How little every membership has to change in a round for `fuzzy_cmeans` to stop
+const FUZZY_TOL: f64 = 1e-9;
+
+/// Clustering algorithm using [fuzzy c-means], where every point belongs to every cluster to some
+/// degree instead of to just one.
+///
+/// Returns the centroids, and a membership matrix where row `i` column `j` is how much
+/// `datapoints[i]` belongs to cluster `j`. Each row is between 0 and 1 and sums to 1.
+///
+/// A point's membership in a cluster falls off with its distance to the centroid, relative to its
+/// distances to the others, raised to the power `2 / (fuzziness - 1)`, and each centroid is the
+/// `WeightedMean` of all the points, weighted by their memberships raised to the power
+/// `fuzziness`. The larger `fuzziness` is, the more evenly points are shared out; 2 is the usual
+/// choice. It must be greater than 1, and anything at or below 1 is treated as the limit of it
+/// approaching 1: each point belongs entirely to its nearest cluster, as in `kmeans`.
+///
+/// A point that lies exactly on a centroid belongs entirely to that cluster. The centroids start
+/// out chosen by k-means++, and rounds continue until no membership changes by more than `1e-9`.
+/// With fewer points than `k`, `k` is lowered to the number of points.
+///
+/// [fuzzy c-means]: https://en.wikipedia.org/wiki/Fuzzy_clustering#Fuzzy_C-means_clustering
+pub fn fuzzy_cmeans<P: WeightedMean>(k: usize, datapoints: Vec<P>, fuzziness: f64) -> (Vec<P>, Vec<Vec<f64>>) {
+    _fuzzy(k, datapoints, fuzziness, &mut thread_rng())
+}
+
+fn _fuzzy<P, R>(k: usize, datapoints: Vec<P>, fuzziness: f64, rng: &mut R) -> (Vec<P>, Vec<Vec<f64>>)
+    where P: WeightedMean, R: Rng + ?Sized
+{
+    if k == 0 || datapoints.is_empty() {
+        return (vec![], vec![]);
+    }
+
+    let k = k.min(datapoints.len());
+    let mut centroids: Vec<P> = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, &Euclidean, rng)
+        .into_iter()
+        .map(Cluster::into_centroids)
+        .collect();
+    let mut memberships = _memberships(&centroids, &datapoints, fuzziness);
+
+    for _ in 0..FUZZY_MAX_ITER {
+        for (j, centroid) in centroids.iter_mut().enumerate() {
+            let weights: Vec<f64> = memberships.iter().map(|row| row[j].powf(fuzziness.max(1.0))).collect();
+            // A cluster nothing belongs to can't say where its centroid should be
+            if weights.iter().sum::<f64>() > 0.0 {
+                *centroid = P::weighted_mean(&datapoints, &weights);
+            }
+        }
+
+        let updated = _memberships(&centroids, &datapoints, fuzziness);
+        let change = updated.iter().flatten().zip(memberships.iter().flatten())
+            .map(|(new, old)| (new - old).abs())
+            .fold(0.0, f64::max);
+        memberships = updated;
+        if change <= FUZZY_TOL {
+            break;
+        }
+    }
+
+    (centroids, memberships)
+}
+
+/// How much each of the `datapoints` belongs to each of the `centroids`, one row per point
+fn _memberships<P: DataPoint>(centroids: &[P], datapoints: &[P], fuzziness: f64) -> Vec<Vec<f64>> {
+    datapoints.iter()
+        .map(|point| {
+            let distances: Vec<f64> = centroids.iter().map(|c| point.dist_sq(c)).collect();
+            let mut row = vec![0.0; centroids.len()];
+
+            // On a centroid, or too close to 1 for the exponent to mean anything, it's all or
+            // nothing
+            if fuzziness <= 1.0 || distances.contains(&0.0) {
+                let nearest = distances.iter().enumerate()
+                    .fold((0, f64::INFINITY), |best, (j, d)| if *d < best.1 { (j, *d) } else { best })
+                    .0;
+                row[nearest] = 1.0;
+                return row;
+            }
+
+            // Distances are squared already, so this is the usual 2 / (m - 1) on plain distances
+            let exponent = 1.0 / (fuzziness - 1.0);
+            for (j, membership) in row.iter_mut().enumerate() {
+                let sum: f64 = distances.iter().map(|d| (distances[j] / d).powf(exponent)).sum();
+                *membership = sum.recip();
+            }
+            row
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, SeedableRng};
+    use super::{_fuzzy, _memberships};
+
+    #[test]
+    fn memberships_sum_to_one() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (9.0, 5.0), (9.0, 6.0), (5.0, 4.0)
+        ];
+
+        for seed in 0..10 {
+            let (mut centroids, memberships) = _fuzzy(2, points.clone(), 2.0, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(memberships.len(), points.len());
+            for row in memberships.iter() {
+                assert_eq!(row.len(), 2);
+                assert!((row.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+                assert!(row.iter().all(|u| (0.0..=1.0).contains(u)));
+            }
+
+            // Each blob belongs mostly to one cluster, and the point between them is shared
+            let first = memberships[0].iter().position(|u| *u > 0.9).unwrap();
+            assert!(memberships[..5].iter().all(|row| row[first] > 0.9));
+            assert!(memberships[5..10].iter().all(|row| row[first] < 0.1));
+            assert!(memberships[10].iter().all(|u| *u > 0.3));
+
+            centroids.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            assert!((centroids[0].0 - 1.6).abs() < 0.5 && (centroids[1].0 - 8.4).abs() < 0.5);
+        }
+    }
+
+    #[test]
+    fn memberships() {
+        let centroids = [(0f64, 0f64), (4.0, 0.0)];
+        let memberships = _memberships(&centroids, &[(0.0, 0.0), (2.0, 5.0), (1.0, 0.0), (4.0, 0.0)], 2.0);
+
+        assert_eq!(memberships[0], vec![1.0, 0.0]);
+        assert_eq!(memberships[1], vec![0.5, 0.5]);
+        // 1 and 3 away, so memberships go as 1 / 1 and 1 / 9
+        assert!((memberships[2][0] - 0.9).abs() < 1e-12 && (memberships[2][1] - 0.1).abs() < 1e-12);
+        assert_eq!(memberships[3], vec![0.0, 1.0]);
+
+        let hard = _memberships(&centroids, &[(1.0, 0.0), (3.0, 1.0)], 1.0);
+        assert_eq!(hard, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+        assert!(_fuzzy(0, vec![(1f64, 1f64)], 2.0, &mut StdRng::seed_from_u64(0)).1.is_empty());
+
+        // More clusters than points
+        let (centroids, memberships) = _fuzzy(4, vec![(1f64, 1f64), (5.0, 5.0)], 2.0, &mut StdRng::seed_from_u64(0));
+        assert_eq!(centroids.len(), 2);
+        assert!(memberships.iter().all(|row| row.len() == 2));
+    }
+}
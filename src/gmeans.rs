@@ -0,0 +1,209 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+use crate::{_centered_covariance, _lloyd, StoppingCriteria};
+use crate::metric::Euclidean;
+use crate::types::*;
+
+/// The Anderson-Darling statistic above which a cluster is taken not to be Gaussian. This is the
+/// critical value for a significance level of 0.0001, as suggested in the paper, so a cluster
+/// that really is Gaussian is split only once in 10,000 tests.
+const GMEANS_CRITICAL_VALUE: f64 = 1.8692;
+
+/// Clusters with fewer points than this are too small to test, and are never split
+const GMEANS_MIN_SIZE: usize = 8;
+
+/// Clustering algorithm that finds k for itself, by g-means, from Hamerly and Elkan's "Learning
+/// the k in k-means", returning k along with the clusters.
+///
+/// It starts with every point in one cluster. Each round, every cluster whose points don't look
+/// like they were drawn from a single Gaussian is split in two: the points are projected onto the
+/// cluster's principal axis (the direction in which they're most spread out), and the projections
+/// are put to an Anderson-Darling normality test. A cluster that fails is seeded with the means of
+/// the points on either side of its centroid along that axis, and 2-means on its points finds the
+/// two halves. Rounds continue until every cluster passes, or there are `max_k` clusters.
+///
+/// Clusters of fewer than 8 points are left as they are. Everything here is deterministic, so the
+/// same points always give the same clustering.
+pub fn gmeans<P: DataPoint + Coordinates>(datapoints: Vec<P>, max_k: usize) -> (usize, Vec<Cluster<P>>) {
+    let centroid = match P::mean(&datapoints) {
+        Some(centroid) if max_k > 0 => centroid,
+        _ => return (0, vec![])
+    };
+
+    let mut clusters = vec![Cluster { centroid, points: datapoints }];
+    // Clusters that have passed the test don't need testing again
+    let mut settled = vec![false];
+    loop {
+        let mut split_any = false;
+        for i in 0..clusters.len() {
+            if settled[i] || clusters.len() >= max_k {
+                continue;
+            }
+
+            match _gaussian_split(&clusters[i]) {
+                Some((first, second)) => {
+                    clusters[i] = first;
+                    clusters.push(second);
+                    settled.push(false);
+                    split_any = true;
+                }
+                None => settled[i] = true
+            }
+        }
+
+        if !split_any {
+            break;
+        }
+    }
+    (clusters.len(), clusters)
+}
+
+/// Splits the cluster in two along its principal axis, unless its points look Gaussian along it
+/// or there are too few of them to tell
+fn _gaussian_split<P: DataPoint + Coordinates>(cluster: &Cluster<P>) -> Option<(Cluster<P>, Cluster<P>)> {
+    if cluster.size() < GMEANS_MIN_SIZE {
+        return None;
+    }
+
+    let projections = _principal_projections(&cluster.points)?;
+    if _anderson_darling(&projections) <= GMEANS_CRITICAL_VALUE {
+        return None;
+    }
+
+    // The projections are centered, so there are points on both sides
+    let side = |below: bool| -> Vec<P> {
+        cluster.points.iter().zip(projections.iter())
+            .filter(|(_, x)| (**x < 0.0) == below)
+            .map(|(p, _)| p.clone())
+            .collect()
+    };
+    let seeds = vec![P::mean(&side(true))?, P::mean(&side(false))?];
+    let mut halves = _lloyd(seeds, &cluster.points, StoppingCriteria::default(), &Euclidean).clusters;
+    let second = halves.pop()?;
+    let first = halves.pop()?;
+    if first.is_empty() || second.is_empty() {
+        return None;
+    }
+    Some((first, second))
+}
+
+/// How far each of the `points` is from their mean along their principal axis, or `None` if
+/// they're all in the same place.
+///
+/// The principal axis, the eigenvector of the points' covariance matrix with the largest
+/// eigenvalue, is found by power iteration, starting from the direction of the point farthest
+/// from the mean.
+fn _principal_projections<P: Coordinates>(points: &[P]) -> Option<Vec<f64>> {
+    let (centered, covariance) = _centered_covariance(points);
+
+    let norm = |v: &[f64]| v.iter().map(|x| x * x).sum::<f64>().sqrt();
+    let mut axis = centered.iter()
+        .max_by(|a, b| norm(a).partial_cmp(&norm(b)).unwrap_or(core::cmp::Ordering::Equal))?
+        .clone();
+    for _ in 0..100 {
+        let length = norm(&axis);
+        if length == 0.0 || length.is_nan() {
+            return None;
+        }
+        let next: Vec<f64> = covariance.iter()
+            .map(|row| row.iter().zip(axis.iter()).map(|(c, a)| c * a).sum::<f64>() / length)
+            .collect();
+        axis = next;
+    }
+
+    let length = norm(&axis);
+    if length == 0.0 || length.is_nan() {
+        return None;
+    }
+    Some(centered.iter().map(|x| x.iter().zip(axis.iter()).map(|(x, a)| x * a).sum::<f64>() / length).collect())
+}
+
+/// The Anderson-Darling statistic for how far `values` are from normally distributed, with their
+/// mean and variance estimated from them, adjusted for the number of values as `A*²`
+fn _anderson_darling(values: &[f64]) -> f64 {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let std_dev = (values.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0)).sqrt();
+    let mut z: Vec<f64> = values.iter().map(|x| (x - mean) / std_dev).collect();
+    z.sort_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+
+    // `1 - Φ(z)` is `Φ(-z)`, which doesn't lose the tail to rounding
+    let sum: f64 = z.iter().zip(z.iter().rev()).enumerate()
+        .map(|(i, (low, high))| {
+            let tails = _normal_cdf(*low).max(f64::MIN_POSITIVE).ln() + _normal_cdf(-high).max(f64::MIN_POSITIVE).ln();
+            (2 * i + 1) as f64 * tails
+        })
+        .sum();
+    let a_sq = -n - sum / n;
+    a_sq * (1.0 + 4.0 / n - 25.0 / (n * n))
+}
+
+/// The standard normal cumulative distribution function, `Φ(x)`, to about seven decimal places.
+///
+/// This uses the approximation of `erfc` in Abramowitz and Stegun, 7.1.26, on `|x|`, so that far
+/// out in the lower tail, where `Φ(x)` is tiny, it's still within a few percent rather than
+/// rounding to zero. That's plenty for the logarithms the Anderson-Darling test takes of it.
+fn _normal_cdf(x: f64) -> f64 {
+    let t = x.abs() / core::f64::consts::SQRT_2;
+    let s = 1.0 / (1.0 + 0.3275911 * t);
+    let poly = s * (0.254829592 + s * (-0.284496736 + s * (1.421413741 + s * (-1.453152027 + s * 1.061405429))));
+    let tail = 0.5 * poly * (-t * t).exp();
+    if x < 0.0 { tail } else { 1.0 - tail }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use super::{_normal_cdf, gmeans};
+
+    /// `n` points drawn from a Gaussian around `center` with a standard deviation of one, by the
+    /// Box-Muller transform
+    fn gaussian(rng: &mut StdRng, center: (f64, f64), n: usize) -> Vec<(f64, f64)> {
+        (0..n)
+            .map(|_| {
+                let (u, v): (f64, f64) = (rng.gen_range(f64::EPSILON..1.0), rng.gen());
+                let r = (-2.0 * u.ln()).sqrt();
+                let theta = 2.0 * core::f64::consts::PI * v;
+                (center.0 + r * theta.cos(), center.1 + r * theta.sin())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_k() {
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let first = gaussian(&mut rng, (0.0, 0.0), 200);
+            let second = gaussian(&mut rng, (20.0, 20.0), 200);
+            let points: Vec<(f64, f64)> = first.iter().chain(second.iter()).cloned().collect();
+
+            let (k, mut clusters) = gmeans(points.clone(), 10);
+            assert_eq!(k, 2);
+            clusters.sort_by(|a, b| a.centroid.partial_cmp(&b.centroid).unwrap());
+            assert_eq!(clusters[0].points, first);
+            assert_eq!(clusters[1].points, second);
+
+            assert_eq!(gmeans(first, 10).0, 1);
+            // Held to one cluster, it can't split at all
+            assert_eq!(gmeans(points, 1).0, 1);
+        }
+
+        assert_eq!(gmeans(Vec::<(f64, f64)>::new(), 5), (0, vec![]));
+        assert_eq!(gmeans(vec![(1.0, 1.0); 20], 5).0, 1);
+    }
+
+    #[test]
+    fn normal_cdf() {
+        assert!((_normal_cdf(0.0) - 0.5).abs() < 1e-7);
+        assert!((_normal_cdf(1.959964) - 0.975).abs() < 1e-7);
+        assert!((_normal_cdf(-1.0) - 0.1586553).abs() < 1e-7);
+        // Far out in the lower tail, it's still roughly right
+        assert!((_normal_cdf(-10.0) / 7.619853e-24 - 1.0).abs() < 0.05);
+    }
+}
@@ -0,0 +1,211 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use rand::prelude::*;
+use crate::_initialize_clusters;
+use crate::metric::Euclidean;
+use crate::types::*;
+
+/// How little the mean log-likelihood per point has to rise in a round for `gmm` to stop
+const GMM_TOL: f64 = 1e-9;
+
+/// The smallest a component's variance can get along an axis, as a fraction of the variance of
+/// all the points along it
+const GMM_VARIANCE_FLOOR: f64 = 1e-6;
+
+/// One Gaussian in a mixture fitted by `gmm`, with its own variance along each axis
+#[derive(Debug, Clone, PartialEq)]
+pub struct GaussianComponent {
+    pub mean: Vec<f64>,
+    pub variances: Vec<f64>,
+    /// The share of all the points the component accounts for. The weights of a mixture's
+    /// components sum to one.
+    pub weight: f64
+}
+
+impl GaussianComponent {
+    /// The log of the component's probability density at `point`, not counting its weight
+    pub fn log_density<P: Coordinates>(&self, point: &P) -> f64 {
+        self.mean.iter().zip(self.variances.iter()).enumerate()
+            .map(|(i, (mean, variance))| {
+                -0.5 * ((2.0 * core::f64::consts::PI * variance).ln() + (point.coordinate(i) - mean).powi(2) / variance)
+            })
+            .sum()
+    }
+}
+
+/// Probabilistic clustering by a Gaussian mixture model, fitted by [expectation-maximization].
+/// Rather than each point belonging to its nearest centroid, it's shared between the components
+/// by how likely each one is to have produced it, so this is k-means where clusters can be
+/// different sizes and spread out differently along each axis.
+///
+/// The means start out chosen by k-means++, with every component spread out as much as all the
+/// points are and weighted equally. Each round, the E step works out every point's responsibilities,
+/// the probability that it came from each component. The M step then sets each component's mean,
+/// variances and weight from the points, weighted by their responsibilities for it. Rounds
+/// continue until the log-likelihood of the points stops rising, or for at most `max_iter` rounds.
+///
+/// A component that settles on a single point, or on points in a line, would have its variance
+/// shrink toward zero and its density grow without bound. To stop that, no variance goes below a
+/// millionth of the variance of all the points along that axis. A component that nothing is
+/// responsible for keeps its mean and variances, with a weight of zero.
+///
+/// With fewer points than `k`, `k` is lowered to the number of points, one component for each.
+///
+/// [expectation-maximization]: https://en.wikipedia.org/wiki/Expectation%E2%80%93maximization_algorithm
+pub fn gmm<P: DataPoint + Coordinates>(k: usize, datapoints: Vec<P>, max_iter: usize) -> Vec<GaussianComponent> {
+    _gmm(k, datapoints, max_iter, &mut thread_rng())
+}
+
+fn _gmm<P, R>(k: usize, datapoints: Vec<P>, max_iter: usize, rng: &mut R) -> Vec<GaussianComponent>
+    where P: DataPoint + Coordinates, R: Rng + ?Sized
+{
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    let k = k.min(datapoints.len());
+    let n = datapoints.len() as f64;
+    let dimensions = datapoints[0].dimensions();
+    let spread: Vec<f64> = (0..dimensions)
+        .map(|i| {
+            let mean = datapoints.iter().map(|p| p.coordinate(i)).sum::<f64>() / n;
+            datapoints.iter().map(|p| (p.coordinate(i) - mean).powi(2)).sum::<f64>() / n
+        })
+        .collect();
+    // Points that are all the same along an axis still need some width there
+    let floors: Vec<f64> = spread.iter().map(|v| (v * GMM_VARIANCE_FLOOR).max(f64::EPSILON)).collect();
+
+    let centroids = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, &Euclidean, rng);
+    let mut components: Vec<GaussianComponent> = centroids.iter()
+        .map(|c| GaussianComponent {
+            mean: (0..dimensions).map(|i| c.centroid.coordinate(i)).collect(),
+            variances: spread.iter().zip(floors.iter()).map(|(v, floor)| v.max(*floor)).collect(),
+            weight: 1.0 / centroids.len() as f64
+        })
+        .collect();
+
+    let mut log_likelihood = f64::NEG_INFINITY;
+    for _ in 0..max_iter {
+        let (responsibilities, updated) = _responsibilities(&components, &datapoints);
+
+        for (j, component) in components.iter_mut().enumerate() {
+            let total: f64 = responsibilities.iter().map(|row| row[j]).sum();
+            component.weight = total / n;
+            if total <= 0.0 {
+                continue;
+            }
+
+            let axes = component.mean.iter_mut().zip(component.variances.iter_mut()).zip(floors.iter());
+            for (i, ((mean, variance), floor)) in axes.enumerate() {
+                *mean = datapoints.iter().zip(responsibilities.iter())
+                    .map(|(p, row)| row[j] * p.coordinate(i))
+                    .sum::<f64>() / total;
+                *variance = datapoints.iter().zip(responsibilities.iter())
+                    .map(|(p, row)| row[j] * (p.coordinate(i) - *mean).powi(2))
+                    .sum::<f64>() / total;
+                *variance = variance.max(*floor);
+            }
+        }
+
+        let improvement = (updated - log_likelihood) / n;
+        log_likelihood = updated;
+        if improvement <= GMM_TOL {
+            break;
+        }
+    }
+    components
+}
+
+/// How responsible each of the `components` is for each of the `datapoints`, one row per point,
+/// along with the log-likelihood of all the points under the mixture
+fn _responsibilities<P: Coordinates>(components: &[GaussianComponent], datapoints: &[P]) -> (Vec<Vec<f64>>, f64) {
+    let mut log_likelihood = 0.0;
+    let responsibilities = datapoints.iter()
+        .map(|point| {
+            let mut row: Vec<f64> = components.iter()
+                .map(|c| if c.weight > 0.0 { c.weight.ln() + c.log_density(point) } else { f64::NEG_INFINITY })
+                .collect();
+            // Far from every component, the densities themselves would all round to zero, so
+            // they're scaled by the largest before leaving logs
+            let largest = row.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            let total: f64 = row.iter().map(|x| (x - largest).exp()).sum();
+            log_likelihood += largest + total.ln();
+            for x in row.iter_mut() {
+                *x = (*x - largest).exp() / total;
+            }
+            row
+        })
+        .collect();
+    (responsibilities, log_likelihood)
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use super::{_gmm, _responsibilities, gmm, GaussianComponent};
+
+    fn fixture() -> Vec<[f64; 3]> {
+        let file = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/test_data.txt")).unwrap();
+        file.trim().lines()
+            .map(|line| {
+                let coords: Vec<f64> = line.split_whitespace().map(|s| s.parse().unwrap()).collect();
+                [coords[0], coords[1], coords[2]]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn two_blobs() {
+        let data = fixture();
+        let (first, second): (Vec<[f64; 3]>, Vec<[f64; 3]>) = data.iter().partition(|p| p[0] < 5.0);
+        let share = first.len() as f64 / data.len() as f64;
+
+        for seed in 0..10 {
+            let mut components = _gmm(2, data.clone(), 100, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(components.len(), 2);
+            components.sort_by(|a, b| a.mean[0].partial_cmp(&b.mean[0]).unwrap());
+
+            assert!((components[0].weight - share).abs() < 0.01, "{:?}", components);
+            assert!((components[0].weight + components[1].weight - 1.0).abs() < 1e-12);
+            for (component, blob) in components.iter().zip([&first, &second]) {
+                for i in 0..3 {
+                    let mean = blob.iter().map(|p| p[i]).sum::<f64>() / blob.len() as f64;
+                    assert!((component.mean[i] - mean).abs() < 0.1, "{:?}", components);
+                    assert!(component.variances[i] > 0.0);
+                }
+            }
+
+            // Each blob's points are all but certainly from their own component
+            let (responsibilities, _) = _responsibilities(&components, &first);
+            assert!(responsibilities.iter().all(|row| row[0] > 0.99));
+        }
+
+        assert!(gmm(0, data.clone(), 100).is_empty());
+        assert!(gmm(2, Vec::<[f64; 3]>::new(), 100).is_empty());
+        // More components than points
+        let components = gmm(5, data[..3].to_vec(), 100);
+        assert_eq!(components.len(), 3);
+        assert!((components.iter().map(|c| c.weight).sum::<f64>() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn variance_floor() {
+        // Two of the points are the same, and one component ends up with just them
+        let points = vec![[0.0, 0.0], [0.0, 0.0], [10.0, 1.0], [11.0, 3.0], [12.0, 2.0]];
+        for seed in 0..10 {
+            let components = _gmm(2, points.clone(), 100, &mut StdRng::seed_from_u64(seed));
+            for component in components.iter() {
+                assert!(component.variances.iter().all(|v| *v > 0.0 && v.is_finite()), "{:?}", components);
+                assert!(component.weight.is_finite() && component.mean.iter().all(|x| x.is_finite()));
+            }
+        }
+
+        let component = GaussianComponent { mean: vec![0.0, 0.0], variances: vec![1.0, 4.0], weight: 1.0 };
+        let expected = -(2.0 * core::f64::consts::PI).ln() - 0.5 * 4f64.ln() - 0.5 * (1.0 + 1.0);
+        assert!((component.log_density(&[1.0, 2.0]) - expected).abs() < 1e-12);
+    }
+}
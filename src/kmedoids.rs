@@ -0,0 +1,212 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use rand::prelude::*;
+use core::cmp::Ordering;
+use crate::_initialize_clusters;
+use crate::metric::{Euclidean, Metric};
+use crate::types::*;
+
+/// Clustering algorithm using the [PAM] (Partitioning Around Medoids) swap heuristic, so that
+/// every cluster is centered on one of the `datapoints` rather than on a mean of them.
+///
+/// The medoids are seeded with k-means++, then the single swap of a medoid for a non-medoid that
+/// lowers the total distance from each point to its nearest medoid the most is made, over and
+/// over, until no swap helps. Only distances are needed, never means, and each returned centroid
+/// is `==` to one of the inputs.
+///
+/// Every pairwise distance is computed up front, so this takes `O(n^2)` memory, and each round
+/// tries `k * (n - k)` swaps. It's meant for small to medium datasets.
+///
+/// [PAM]: https://en.wikipedia.org/wiki/K-medoids#Partitioning_Around_Medoids_(PAM)
+pub fn kmedoids<P: DataPoint>(k: usize, datapoints: Vec<P>) -> Vec<Cluster<P>> {
+    _pam(k, datapoints, &Euclidean, &mut thread_rng())
+}
+
+/// `kmedoids`, measuring distances with `metric` instead of `DataPoint::dist`.
+///
+/// Since no mean is ever taken, the points don't even have to be `DataPoint`s, so this can cluster
+/// things with only a notion of distance between them, like sets of tags with `Jaccard`.
+pub fn kmedoids_with_metric<P, M>(k: usize, datapoints: Vec<P>, metric: M) -> Vec<Cluster<P>>
+    where P: Clone + PartialEq, M: Metric<P>
+{
+    _pam(k, datapoints, &metric, &mut thread_rng())
+}
+
+fn _pam<P, M, R>(k: usize, datapoints: Vec<P>, metric: &M, rng: &mut R) -> Vec<Cluster<P>>
+    where P: Clone + PartialEq, M: Metric<P>, R: Rng + ?Sized
+{
+    let n = datapoints.len();
+    if k == 0 || n == 0 {
+        return vec![];
+    }
+
+    let mut distances = vec![0.0; n * n];
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let d = metric.distance(&datapoints[i], &datapoints[j]);
+            distances[i * n + j] = d;
+            distances[j * n + i] = d;
+        }
+    }
+
+    // The total distance from every point to its nearest medoid
+    let cost = |medoids: &[usize]| -> f64 {
+        (0..n)
+            .map(|i| medoids.iter().map(|m| distances[i * n + m]).fold(f64::INFINITY, f64::min))
+            .sum()
+    };
+
+    let mut medoids: Vec<usize> = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, metric, rng)
+        .iter()
+        .map(|c| datapoints.iter().position(|p| *p == c.centroid).unwrap())
+        .collect();
+    let mut current_cost = cost(&medoids);
+
+    loop {
+        let mut best_swap = None;
+        let mut best_cost = current_cost;
+        for m in 0..medoids.len() {
+            for candidate in 0..n {
+                if medoids.contains(&candidate) {
+                    continue;
+                }
+
+                let mut trial = medoids.clone();
+                trial[m] = candidate;
+                let trial_cost = cost(&trial);
+                if trial_cost < best_cost {
+                    best_swap = Some((m, candidate));
+                    best_cost = trial_cost;
+                }
+            }
+        }
+
+        match best_swap {
+            Some((m, candidate)) => {
+                medoids[m] = candidate;
+                current_cost = best_cost;
+            }
+            None => break
+        }
+    }
+
+    // A point exactly as close to two medoids goes to whichever comes first in `datapoints`, so
+    // it doesn't matter what order they were picked in
+    let labels: Vec<usize> = (0..n)
+        .map(|i| {
+            (0..medoids.len())
+                .min_by(|a, b| {
+                    distances[i * n + medoids[*a]].partial_cmp(&distances[i * n + medoids[*b]])
+                        .unwrap_or(Ordering::Equal)
+                        .then(medoids[*a].cmp(&medoids[*b]))
+                })
+                .unwrap()
+        })
+        .collect();
+
+    let mut clusters: Vec<Cluster<P>> = medoids.iter().map(|m| Cluster::new(&datapoints[*m])).collect();
+    for (point, label) in datapoints.into_iter().zip(labels) {
+        clusters[label].points.push(point);
+    }
+    clusters
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::metric::{DynamicTimeWarping, Euclidean, Jaccard};
+    use crate::types::{Cluster, DataPoint};
+    use super::{_pam, kmedoids, kmedoids_with_metric};
+
+    #[test]
+    fn medoids_are_input_points() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ];
+
+        let clusters = kmedoids(3, points.clone());
+        assert_eq!(clusters.len(), 3);
+        for centroid in Cluster::centroids(&clusters) {
+            assert!(points.contains(&centroid));
+        }
+        assert_eq!(clusters.iter().map(|c| c.points.len()).sum::<usize>(), points.len());
+    }
+
+    #[test]
+    fn finds_best_medoids() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (9.0, 5.0), (9.0, 6.0)
+        ];
+        let cost = |medoids: &[(f64, f64)]| -> f64 {
+            points.iter()
+                .map(|p| medoids.iter().map(|m| m.dist(p)).fold(f64::INFINITY, f64::min))
+                .sum()
+        };
+
+        // Small enough to check every pair of medoids
+        let mut best = f64::INFINITY;
+        for a in 0..points.len() {
+            for b in (a + 1)..points.len() {
+                best = best.min(cost(&[points[a], points[b]]));
+            }
+        }
+
+        for seed in 0..10 {
+            let clusters = _pam(2, points.clone(), &Euclidean, &mut StdRng::seed_from_u64(seed));
+            let medoids: Vec<(f64, f64)> = Cluster::centroids(&clusters);
+            assert_eq!(cost(&medoids), best);
+        }
+    }
+
+    #[test]
+    fn tag_sets() {
+        let (rust, go, python) = (1u32, 2, 3);
+        let (cooking, baking, recipes) = (10u32, 11, 12);
+        let sets = vec![
+            vec![rust, go], vec![rust, go, python], vec![go, python], vec![rust, python],
+            vec![cooking, baking], vec![cooking, recipes], vec![baking, recipes], vec![cooking, baking, recipes]
+        ];
+
+        let clusters = kmedoids_with_metric(2, sets.clone(), Jaccard);
+        let mut groups: Vec<Vec<Vec<u32>>> = clusters.iter().map(|c| c.points.clone()).collect();
+        groups.sort();
+        assert_eq!(groups, vec![sets[..4].to_vec(), sets[4..].to_vec()]);
+        for cluster in clusters {
+            assert!(sets.contains(&cluster.centroid));
+        }
+    }
+
+    #[test]
+    fn time_series() {
+        // Slow waves, one cycle long, and fast ones, three cycles long, each sampled at a different
+        // rate and starting at a different phase
+        let wave = |cycles: f64, samples: usize, phase: f64| -> Vec<f64> {
+            (0..samples)
+                .map(|i| (cycles * core::f64::consts::TAU * i as f64 / samples as f64 + phase).sin())
+                .collect()
+        };
+        let slow: Vec<Vec<f64>> = (0..5).map(|i| wave(1.0, 40 + 3 * i, 0.1 * i as f64)).collect();
+        let fast: Vec<Vec<f64>> = (0..5).map(|i| wave(3.0, 45 + 2 * i, 0.15 * i as f64)).collect();
+        let series: Vec<Vec<f64>> = slow.iter().chain(fast.iter()).cloned().collect();
+
+        for seed in 0..10 {
+            let clusters = _pam(2, series.clone(), &DynamicTimeWarping, &mut StdRng::seed_from_u64(seed));
+            let mut groups: Vec<Vec<Vec<f64>>> = clusters.iter().map(|c| c.points.clone()).collect();
+            groups.sort_by_key(|g| series.iter().position(|s| *s == g[0]));
+            assert_eq!(groups, vec![slow.clone(), fast.clone()]);
+            for cluster in clusters {
+                assert!(series.contains(&cluster.centroid));
+            }
+        }
+    }
+}
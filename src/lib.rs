@@ -2,146 +2,2838 @@
 
 #[cfg(feature = "alloc")]
 #[macro_use] extern crate alloc;
+// So that `#[derive(DataPoint)]`'s `::kmeans` paths work in this crate's own tests
+#[cfg(feature = "derive")]
+extern crate self as kmeans;
 
+#[cfg(feature = "thread_rng")]
+mod bisecting;
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod builder;
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod ckmeans;
+#[cfg(feature = "thread_rng")]
+mod constrained;
+#[cfg(feature = "thread_rng")]
+mod dense;
+#[cfg(feature = "thread_rng")]
+mod elkan;
+#[cfg(feature = "heapless")]
+mod fixed;
+#[cfg(feature = "thread_rng")]
+mod fuzzy;
+pub mod metric;
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod gmeans;
+#[cfg(feature = "thread_rng")]
+mod gmm;
+#[cfg(feature = "thread_rng")]
+mod kmedoids;
+#[cfg(feature = "std")]
+mod loader;
+#[cfg(feature = "thread_rng")]
+mod mahalanobis;
+#[cfg(feature = "thread_rng")]
+mod minibatch;
+#[cfg(any(feature = "std", feature = "alloc"))]
+mod scaling;
+#[cfg(feature = "thread_rng")]
+mod spherical;
+#[cfg(feature = "thread_rng")]
+mod weighted;
+#[cfg(feature = "thread_rng")]
+mod xmeans;
 pub mod types;
 
 #[cfg(feature = "alloc")]
-use alloc::vec::Vec;
+use alloc::{string::String, vec::Vec};
 
+#[cfg(all(feature = "alloc", not(feature = "std")))]
+use num_traits::Float;
+#[cfg(any(feature = "std", feature = "alloc", feature = "heapless"))]
 use rand::prelude::*;
+use metric::*;
 use types::*;
+
+#[cfg(feature = "thread_rng")]
+pub use bisecting::bisecting_kmeans;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use builder::KMeans;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use ckmeans::kmeans_1d_optimal;
+#[cfg(feature = "thread_rng")]
+pub use constrained::{balanced_kmeans, constrained_kmeans};
+#[cfg(feature = "thread_rng")]
+pub use dense::kmeans_dense;
+#[cfg(feature = "thread_rng")]
+pub use elkan::kmeans_elkan;
+#[cfg(feature = "heapless")]
+pub use fixed::kmeans_fixed;
+#[cfg(feature = "thread_rng")]
+pub use fuzzy::fuzzy_cmeans;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use gmeans::gmeans;
+#[cfg(feature = "thread_rng")]
+pub use gmm::{gmm, GaussianComponent};
+#[cfg(feature = "thread_rng")]
+pub use kmedoids::{kmedoids, kmedoids_with_metric};
+#[cfg(feature = "std")]
+pub use loader::{load_points_from_reader, ParseError};
+#[cfg(feature = "thread_rng")]
+pub use mahalanobis::kmeans_mahalanobis;
+#[cfg(feature = "thread_rng")]
+pub use minibatch::minibatch_kmeans;
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub use scaling::{minmax_scale, standardize, MinMaxScaling, Standardization};
+#[cfg(feature = "thread_rng")]
+pub use spherical::spherical_kmeans;
+#[cfg(feature = "thread_rng")]
+pub use weighted::{merge_clusters, weighted_kmeans};
+#[cfg(feature = "thread_rng")]
+pub use xmeans::xmeans;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use core::fmt::{Debug, Write};
+#[cfg(any(feature = "std", feature = "alloc"))]
 use core::ops::Add;
+#[cfg(feature = "thread_rng")]
+use core::ops::RangeInclusive;
+
+pub mod prelude {
+    pub use super::{
+        metric::{Chebyshev, Cosine, DynamicTimeWarping, Euclidean, Haversine, Manhattan, Metric, Minkowski},
+        types::{Coordinates, DataPoint, Field, InitMethod, KMeansError, Median, Normalize, Rgb, ToFloat, WeightedMean}
+    };
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub use super::{
+        KMeans,
+        MinMaxScaling,
+        Standardization,
+        aic,
+        assign,
+        bic,
+        davies_bouldin_index,
+        gmeans,
+        initialize_centroids_with_rng,
+        kmeans_1d_optimal,
+        kmeans_from_centroids,
+        kmeans_seeded,
+        kmeans_with_rng,
+        minmax_scale,
+        predict,
+        predict_batch,
+        predict_batch_with_distance,
+        predict_with_distance,
+        silhouette_score,
+        standardize,
+        summary,
+        total_inertia,
+        transform,
+        metric::{Gower, Jaccard, Mahalanobis},
+        types::{Cluster, KMeansResult, MixedPoint}
+    };
+
+    #[cfg(feature = "thread_rng")]
+    pub use super::{
+        GaussianComponent,
+        auto_kmeans,
+        auto_kmeans_with_score,
+        balanced_kmeans,
+        bisecting_kmeans,
+        constrained_kmeans,
+        fit_predict,
+        fuzzy_cmeans,
+        gmm,
+        initialize_centroids,
+        kmeans,
+        kmeans_best_of,
+        kmeans_centroids_only,
+        kmeans_detailed,
+        kmeans_dense,
+        kmeans_elkan,
+        kmeans_float_centroids,
+        kmeans_from_iter,
+        kmeans_geographic,
+        kmeans_labels,
+        kmeans_mahalanobis,
+        kmeans_ref,
+        kmeans_rounded_centroids,
+        kmeans_with_callback,
+        kmeans_with_deadline,
+        kmeans_with_init,
+        kmeans_with_max_iter,
+        kmeans_with_metric,
+        kmeans_with_tol,
+        kmedians,
+        kmedoids,
+        kmedoids_with_metric,
+        merge_clusters,
+        minibatch_kmeans,
+        quantize,
+        spherical_kmeans,
+        try_kmeans,
+        weighted_kmeans,
+        xmeans
+    };
+
+    #[cfg(all(feature = "ndarray", feature = "thread_rng"))]
+    pub use super::kmeans_ndarray;
+
+    #[cfg(feature = "heapless")]
+    pub use super::{kmeans_fixed, types::FixedCluster};
+
+    #[cfg(feature = "std")]
+    pub use super::{load_points_from_reader, ParseError};
+}
+
+/// Clustering algorithm using k-means++
+#[cfg(feature = "thread_rng")]
+pub fn kmeans<P: DataPoint>(k: usize, datapoints: Vec<P>) -> Vec<Cluster<P>> {
+    kmeans_ref(k, &datapoints)
+}
+
+/// Clustering algorithm using k-means++ on borrowed `datapoints`, for callers that want to keep
+/// them. Each point is cloned once, into the cluster it ends up in.
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_ref<P: DataPoint>(k: usize, datapoints: &[P]) -> Vec<Cluster<P>> {
+    _kmeans(k, datapoints, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &Euclidean, &mut thread_rng()).clusters
+}
+
+/// Clustering algorithm using k-means++ on whatever `datapoints` yields, so that points coming
+/// from an iterator don't have to be collected first
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_from_iter<P: DataPoint, I: IntoIterator<Item = P>>(k: usize, datapoints: I) -> Vec<Cluster<P>> {
+    kmeans(k, datapoints.into_iter().collect())
+}
+
+/// Clustering algorithm using k-means++ that checks its arguments instead of panicking on them
+#[cfg(feature = "thread_rng")]
+pub fn try_kmeans<P: DataPoint>(k: usize, datapoints: Vec<P>) -> Result<Vec<Cluster<P>>, KMeansError> {
+    if datapoints.is_empty() {
+        return Err(KMeansError::NoData);
+    }
+    if k > datapoints.len() {
+        return Err(KMeansError::TooFewPoints { k, n: datapoints.len() });
+    }
+
+    Ok(kmeans(k, datapoints))
+}
+
+/// Clustering algorithm using k-means++ that converts the `datapoints` to `f64` coordinates first,
+/// so that each centroid is the exact mean of its points.
+///
+/// `kmeans` on integer points rounds every centroid back to an integer after every round, which
+/// can leave it off the true mean and, in turn, send points to the wrong cluster. Here, the
+/// clusters hold the converted points, which are exact for integers up to 2^53 in magnitude.
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_float_centroids<P: ToFloat>(k: usize, datapoints: Vec<P>) -> Vec<Cluster<P::Float>> {
+    kmeans(k, datapoints.iter().map(P::to_float).collect())
+}
+
+/// Clustering algorithm using k-means++ that keeps each centroid at the exact `f64` mean of its
+/// points while iterating, as `kmeans_float_centroids` does, but gives back clusters of the
+/// original points. The centroids are rounded to the nearest point of the original type once, at
+/// the end.
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_rounded_centroids<P: ToFloat>(k: usize, datapoints: Vec<P>) -> Vec<Cluster<P>> {
+    _rounded_centroids(k, datapoints, &mut thread_rng())
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _rounded_centroids<P: ToFloat, R: Rng + ?Sized>(k: usize, datapoints: Vec<P>, rng: &mut R) -> Vec<Cluster<P>> {
+    let floats: Vec<P::Float> = datapoints.iter().map(P::to_float).collect();
+    let result = _kmeans(k, &floats, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &Euclidean, rng);
+
+    let mut clusters: Vec<Cluster<P>> = result.clusters.iter()
+        .map(|c| Cluster::new(&P::from_float(&c.centroid)))
+        .collect();
+    for (point, label) in datapoints.into_iter().zip(result.labels) {
+        clusters[label].points.push(point);
+    }
+    clusters
+}
+
+/// Clustering algorithm using k-means++ on the rows of an `ndarray` matrix of shape
+/// `(n_samples, n_features)`, with the `ndarray` feature. Each row is copied into its own
+/// `Array1` to be clustered.
+#[cfg(all(feature = "ndarray", feature = "thread_rng"))]
+pub fn kmeans_ndarray(k: usize, data: ndarray::ArrayView2<f64>) -> Vec<Cluster<ndarray::Array1<f64>>> {
+    kmeans(k, data.rows().into_iter().map(|row| row.to_owned()).collect())
+}
+
+/// Clustering algorithm using k-means++, seeded so that the same `seed` and `datapoints` always
+/// produce the same clustering
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn kmeans_seeded<P: DataPoint>(k: usize, datapoints: Vec<P>, seed: u64) -> Vec<Cluster<P>> {
+    kmeans_with_rng(k, datapoints, &mut StdRng::seed_from_u64(seed))
+}
+
+/// Clustering algorithm using k-means++, drawing all of its randomness from `rng`
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn kmeans_with_rng<P: DataPoint, R: Rng + ?Sized>(k: usize, datapoints: Vec<P>, rng: &mut R) -> Vec<Cluster<P>> {
+    _kmeans(k, &datapoints, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &Euclidean, rng).clusters
+}
+
+/// Clustering algorithm that chooses its starting centroids with `init` rather than always using
+/// k-means++
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_with_init<P: DataPoint>(k: usize, datapoints: Vec<P>, init: InitMethod) -> Vec<Cluster<P>> {
+    _kmeans(k, &datapoints, init, StoppingCriteria::default(), &Euclidean, &mut thread_rng()).clusters
+}
+
+/// Clustering algorithm using k-means++ that gives up after `max_iter` rounds of recentering and
+/// reassignment.
+///
+/// If the cap is reached, the clustering has not converged: the returned clusters are simply the
+/// state of the algorithm after the last round.
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_with_max_iter<P: DataPoint>(k: usize, datapoints: Vec<P>, max_iter: usize) -> Vec<Cluster<P>> {
+    _kmeans(k, &datapoints, InitMethod::KMeansPlusPlus, StoppingCriteria { max_iter, ..Default::default() }, &Euclidean, &mut thread_rng()).clusters
+}
+
+/// Clustering algorithm using k-means++ that also returns the index of the cluster each of the
+/// `datapoints` was assigned to, in input order
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_labels<P: DataPoint>(k: usize, datapoints: Vec<P>) -> (Vec<Cluster<P>>, Vec<usize>) {
+    let result = _kmeans(k, &datapoints, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &Euclidean, &mut thread_rng());
+    (result.clusters, result.labels)
+}
+
+/// Clustering algorithm using k-means++ that stops once no centroid moves by `tol` or more in a
+/// round, rather than waiting for the assignments to stop changing entirely
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_with_tol<P: DataPoint>(k: usize, datapoints: Vec<P>, tol: f64) -> Vec<Cluster<P>> {
+    _kmeans(k, &datapoints, InitMethod::KMeansPlusPlus, StoppingCriteria { tol, ..Default::default() }, &Euclidean, &mut thread_rng()).clusters
+}
+
+/// Clustering algorithm using k-means++ that also reports how many rounds it ran and whether it
+/// converged before reaching `max_iter`.
+///
+/// With `record_history`, the `total_inertia` at the end of every round is recorded in
+/// `inertia_history` too, one entry per iteration, to see how quickly the clustering settled.
+/// Without it, the history is left empty and costs nothing. It usually falls from one round to the
+/// next, but isn't sure to: integer centroids are rounded toward zero rather than sitting at the
+/// mean of their points, and a clustering that goes around in a cycle is recorded doing so.
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_detailed<P: DataPoint>(k: usize, datapoints: Vec<P>, max_iter: usize, record_history: bool) -> KMeansResult<P> {
+    let stop = StoppingCriteria { max_iter, ..Default::default() };
+    if !record_history {
+        return _kmeans(k, &datapoints, InitMethod::KMeansPlusPlus, stop, &Euclidean, &mut thread_rng());
+    }
+    if k == 0 || datapoints.is_empty() {
+        return KMeansResult { clusters: vec![], labels: vec![], iterations: 0, converged: true, inertia: 0.0, inertia_history: vec![] };
+    }
+
+    let centroids = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, &Euclidean, &mut thread_rng())
+        .into_iter()
+        .map(Cluster::into_centroids)
+        .collect();
+    let mut inertia_history = vec![];
+    let (clusters, labels, iterations, converged) = _lloyd_observed(centroids, &datapoints, stop, &Euclidean, P::mean_refs, &mut |_, clusters| {
+        inertia_history.push(clusters.iter().map(|c| c.inertia(&datapoints)).sum())
+    });
+    let inertia = _intermediate_inertia(&clusters, &datapoints, &Euclidean);
+    let clusters = clusters.iter()
+        .map(|c| Cluster::from_intermediate(c, &datapoints))
+        .collect();
+    KMeansResult { clusters, labels, iterations, converged, inertia, inertia_history }
+}
+
+/// Clustering algorithm using k-means++ that stops starting new rounds once `deadline` has
+/// passed, returning the clustering as it stands with `converged` set to `false`.
+///
+/// The clock is checked once before each round, so the round running when the deadline passes is
+/// allowed to finish, and choosing the starting centroids isn't interrupted at all.
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_with_deadline<P: DataPoint>(k: usize, datapoints: Vec<P>, deadline: std::time::Instant) -> KMeansResult<P> {
+    _kmeans(k, &datapoints, InitMethod::KMeansPlusPlus, StoppingCriteria { deadline: Some(deadline), ..Default::default() }, &Euclidean, &mut thread_rng())
+}
+
+/// Clustering algorithm using k-means++ that measures distances with `metric` instead of
+/// `DataPoint::dist`.
+///
+/// Centroids are still recalculated with `DataPoint::mean`.
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_with_metric<P: DataPoint, M: Metric<P>>(k: usize, datapoints: Vec<P>, metric: M) -> Vec<Cluster<P>> {
+    _kmeans(k, &datapoints, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &metric, &mut thread_rng()).clusters
+}
+
+/// Clustering algorithm using k-means++ that returns only the index of the cluster each of the
+/// `datapoints` was assigned to, in input order, without building the clusters themselves
+#[cfg(feature = "thread_rng")]
+pub fn fit_predict<P: DataPoint>(k: usize, datapoints: Vec<P>) -> Vec<usize> {
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    let centroids = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, &Euclidean, &mut thread_rng())
+        .into_iter()
+        .map(Cluster::into_centroids)
+        .collect();
+    let (_, labels, ..) = _lloyd_indices(centroids, &datapoints, StoppingCriteria::default(), &Euclidean, P::mean_refs);
+    labels
+}
+
+/// Clustering algorithm using k-means++ that calls `callback` at the end of each round of
+/// recentering and reassignment, with the number of rounds run so far (starting from 1) and the
+/// `total_inertia` of the clustering at that point. Useful for showing progress on a long run.
+///
+/// The inertia usually falls from one round to the next, but isn't sure to, for the same reasons
+/// as `kmeans_detailed`'s history. The last value passed is always that of the clusters returned.
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_with_callback<P: DataPoint, F: FnMut(usize, f64)>(k: usize, datapoints: Vec<P>, mut callback: F) -> Vec<Cluster<P>> {
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    let centroids = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, &Euclidean, &mut thread_rng())
+        .into_iter()
+        .map(Cluster::into_centroids)
+        .collect();
+    let (clusters, ..) = _lloyd_observed(centroids, &datapoints, StoppingCriteria::default(), &Euclidean, P::mean_refs, &mut |iteration, clusters| {
+        callback(iteration, clusters.iter().map(|c| c.inertia(&datapoints)).sum())
+    });
+    clusters.iter()
+        .map(|c| Cluster::from_intermediate(c, &datapoints))
+        .collect()
+}
+
+/// Clustering algorithm using k-means++ that returns only each cluster's centroid and how many of
+/// the `datapoints` were assigned to it.
+///
+/// Unlike `kmeans`, the clusters never hold copies of their points, so the result is `k` entries
+/// long however many points there are, and the clustering needs little more memory than
+/// `datapoints` itself.
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_centroids_only<P: DataPoint>(k: usize, datapoints: Vec<P>) -> Vec<(P, usize)> {
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    let centroids = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, &Euclidean, &mut thread_rng())
+        .into_iter()
+        .map(Cluster::into_centroids)
+        .collect();
+    let (clusters, ..) = _lloyd_indices(centroids, &datapoints, StoppingCriteria::default(), &Euclidean, P::mean_refs);
+    clusters.into_iter()
+        .map(|c| (c.centroid, c.point_indices.len()))
+        .collect()
+}
+
+/// Chooses `k` starting centroids from the `datapoints` with k-means++, the same way `kmeans` does,
+/// without going on to cluster them. Useful for seeding some other algorithm, or for
+/// `kmeans_from_centroids`.
+///
+/// The centroids are `k` different points from `datapoints`, or all of them if there are fewer
+/// than `k`.
+#[cfg(feature = "thread_rng")]
+pub fn initialize_centroids<P: DataPoint>(k: usize, datapoints: &[P]) -> Vec<P> {
+    initialize_centroids_with_rng(k, datapoints, &mut thread_rng())
+}
+
+/// `initialize_centroids`, drawing all of its randomness from `rng`
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn initialize_centroids_with_rng<P: DataPoint, R: Rng + ?Sized>(k: usize, datapoints: &[P], rng: &mut R) -> Vec<P> {
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    _initialize_clusters(k.min(datapoints.len()), datapoints, InitMethod::KMeansPlusPlus, &Euclidean, rng)
+        .into_iter()
+        .map(Cluster::into_centroids)
+        .collect()
+}
+
+/// Clustering algorithm that starts from the given `centroids` instead of choosing its own with
+/// k-means++. The number of clusters is the number of `centroids`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn kmeans_from_centroids<P: DataPoint>(centroids: Vec<P>, datapoints: Vec<P>) -> Result<Vec<Cluster<P>>, KMeansError> {
+    if centroids.is_empty() {
+        return Err(KMeansError::NoCentroids);
+    }
+    if centroids.len() > datapoints.len() {
+        return Err(KMeansError::TooFewPoints { k: centroids.len(), n: datapoints.len() });
+    }
+
+    Ok(_lloyd(centroids, &datapoints, StoppingCriteria::default(), &Euclidean).clusters)
+}
+
+/// Clustering algorithm like `kmeans`, but measuring distances with `Manhattan` and recentering
+/// each cluster on the per-coordinate median of its points.
+///
+/// Medians are much less sensitive to outliers than means, so one far-off point won't drag its
+/// cluster's centroid away from the rest.
+#[cfg(feature = "thread_rng")]
+pub fn kmedians<P: Median + Coordinates>(k: usize, datapoints: Vec<P>) -> Vec<Cluster<P>> {
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    let clusters = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, &Manhattan, &mut thread_rng());
+    let centroids = clusters.into_iter().map(Cluster::into_centroids).collect();
+    _lloyd_with_center(centroids, &datapoints, StoppingCriteria::default(), &Manhattan, |ps| {
+        // There's no median of nothing, so an empty cluster's centroid stays put
+        let ps: Vec<P> = ps.iter().map(|p| (*p).clone()).collect();
+        (!ps.is_empty()).then(|| P::median(&ps))
+    }).clusters
+}
+
+/// Clustering algorithm like `kmeans_with_metric` with `Haversine`, for `(latitude, longitude)`
+/// points in degrees, but recentering each cluster on the mean direction of its points from the
+/// center of the sphere rather than on the mean of their latitudes and longitudes.
+///
+/// That mean is where the great circle between two points meets its midpoint, and it handles
+/// clusters that straddle the 180th meridian or surround a pole, where averaging the degrees goes
+/// badly wrong.
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_geographic(k: usize, datapoints: Vec<(f64, f64)>, metric: Haversine) -> Vec<Cluster<(f64, f64)>> {
+    _geographic(k, datapoints, metric, &mut thread_rng())
+}
+
+#[cfg(feature = "thread_rng")]
+fn _geographic<R: Rng + ?Sized>(k: usize, datapoints: Vec<(f64, f64)>, metric: Haversine, rng: &mut R) -> Vec<Cluster<(f64, f64)>> {
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    let clusters = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, &metric, rng);
+    let centroids = clusters.into_iter().map(Cluster::into_centroids).collect();
+    _lloyd_with_center(centroids, &datapoints, StoppingCriteria::default(), &metric, _geographic_mean).clusters
+}
+
+/// The point on the sphere in the mean direction of the `(latitude, longitude)` points, or `None`
+/// if there are no points or their directions cancel out
+#[cfg(feature = "thread_rng")]
+fn _geographic_mean(ps: &[&(f64, f64)]) -> Option<(f64, f64)> {
+    let (mut x, mut y, mut z) = (0f64, 0f64, 0f64);
+    for (lat, lon) in ps.iter() {
+        let (lat, lon) = (lat.to_radians(), lon.to_radians());
+        x += lat.cos() * lon.cos();
+        y += lat.cos() * lon.sin();
+        z += lat.sin();
+    }
+    if x * x + y * y + z * z < 1e-18 {
+        return None;
+    }
+
+    Some((z.atan2((x * x + y * y).sqrt()).to_degrees(), y.atan2(x).to_degrees()))
+}
+
+/// Reduces the colors of an image to a palette of `palette_size` colors, by clustering its
+/// `image_pixels` with k-means++. Each cluster's centroid is a palette color, and its points are
+/// the pixels to be drawn in it.
+///
+/// An image with fewer pixels than `palette_size` gets a palette color for each pixel.
+#[cfg(feature = "thread_rng")]
+pub fn quantize(image_pixels: Vec<Rgb>, palette_size: usize) -> Vec<Cluster<Rgb>> {
+    kmeans(palette_size.min(image_pixels.len()), image_pixels)
+}
+
+/// Clustering algorithm using k-means++ that runs `n_init` times and keeps the clustering with the
+/// lowest `total_inertia`.
+///
+/// Each run draws a fresh initialization, so this guards against a single unlucky start landing in
+/// a poor local optimum. An `n_init` of 0 is treated as 1.
+#[cfg(feature = "thread_rng")]
+pub fn kmeans_best_of<P: DataPoint>(k: usize, datapoints: Vec<P>, n_init: usize) -> Vec<Cluster<P>> {
+    _best_of(k, datapoints, n_init, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &mut thread_rng()).clusters
+}
+
+/// How many times `auto_kmeans` runs k-means for each k, keeping the best
+#[cfg(feature = "thread_rng")]
+const AUTO_N_INIT: usize = 10;
+
+/// Clustering algorithm that tries every k in `k_range` and keeps whichever clustering has the
+/// highest `silhouette_score`, returning that k along with it.
+///
+/// Each k gets the best of 10 k-means++ runs, as in `kmeans_best_of`, so this does `10 *
+/// k_range.len()` fits, plus scoring each one, which takes `O(n^2)` time for the silhouette. A
+/// k of 1 always scores 0, so it's only chosen if no split scores any better. A `k_range` reaching
+/// past the number of points stops at it. If `k_range` is empty, the result is `(0, vec![])`.
+#[cfg(feature = "thread_rng")]
+pub fn auto_kmeans<P: DataPoint>(k_range: RangeInclusive<usize>, datapoints: Vec<P>) -> (usize, Vec<Cluster<P>>) {
+    auto_kmeans_with_score(k_range, datapoints, silhouette_score)
+}
+
+/// `auto_kmeans`, but choosing the clustering that `score` rates highest instead of using
+/// `silhouette_score`. For scores where lower is better, like `davies_bouldin_index`, negate them.
+#[cfg(feature = "thread_rng")]
+pub fn auto_kmeans_with_score<P, F>(k_range: RangeInclusive<usize>, datapoints: Vec<P>, score: F) -> (usize, Vec<Cluster<P>>)
+    where P: DataPoint, F: Fn(&[Cluster<P>]) -> f64
+{
+    _auto(k_range, datapoints, score, &mut thread_rng())
+}
+
+#[cfg(feature = "thread_rng")]
+fn _auto<P, F, R>(k_range: RangeInclusive<usize>, datapoints: Vec<P>, score: F, rng: &mut R) -> (usize, Vec<Cluster<P>>)
+    where P: DataPoint, F: Fn(&[Cluster<P>]) -> f64, R: Rng + ?Sized
+{
+    let mut best = (0, vec![]);
+    let mut best_score = f64::NEG_INFINITY;
+    // There's no splitting the points into more clusters than there are points
+    let (low, high) = k_range.into_inner();
+    for k in low..=high.min(datapoints.len()) {
+        let clusters = _best_of(k, datapoints.clone(), AUTO_N_INIT, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), rng).clusters;
+        let score = score(&clusters);
+        if score > best_score {
+            best = (k, clusters);
+            best_score = score;
+        }
+    }
+
+    best
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _best_of<P, R>(k: usize, datapoints: Vec<P>, n_init: usize, init: InitMethod, stop: StoppingCriteria, rng: &mut R) -> KMeansResult<P>
+    where P: DataPoint, R: Rng + ?Sized
+{
+    // Every run keeps drawing from the same `rng`, so each one gets a different initialization
+    let mut best = _kmeans(k, &datapoints, init, stop, &Euclidean, rng);
+    for _ in 1..n_init {
+        let result = _kmeans(k, &datapoints, init, stop, &Euclidean, rng);
+        if result.inertia < best.inertia {
+            best = result;
+        }
+    }
+
+    best
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _kmeans<P, M, R>(k: usize, datapoints: &[P], init: InitMethod, stop: StoppingCriteria, metric: &M, rng: &mut R) -> KMeansResult<P>
+    where P: DataPoint, M: Metric<P>, R: Rng + ?Sized
+{
+    // Nothing to cluster, or nothing to cluster into
+    if k == 0 || datapoints.is_empty() {
+        return KMeansResult { clusters: vec![], labels: vec![], iterations: 0, converged: true, inertia: 0.0, inertia_history: vec![] };
+    }
+
+    // Initialize cluster means
+    let clusters = _initialize_clusters(k, datapoints, init, metric, rng);
+
+    _lloyd(clusters.into_iter().map(Cluster::into_centroids).collect(), datapoints, stop, metric)
+}
+
+/// When the main loop should stop waiting for the clustering to settle
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Debug, Clone, Copy)]
+struct StoppingCriteria {
+    /// The most recenter/reassign rounds to run
+    max_iter: usize,
+    /// How far the centroids have to move in a round to be worth running another
+    tol: f64,
+    /// When to stop starting new rounds, however far from settling the clustering is
+    #[cfg(feature = "std")]
+    deadline: Option<std::time::Instant>
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl StoppingCriteria {
+    /// Whether there's a deadline and it has passed
+    fn past_deadline(&self) -> bool {
+        #[cfg(feature = "std")]
+        {
+            self.deadline.is_some_and(|deadline| std::time::Instant::now() >= deadline)
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            false
+        }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Default for StoppingCriteria {
+    fn default() -> Self {
+        Self {
+            max_iter: usize::MAX,
+            tol: 0.0,
+            #[cfg(feature = "std")]
+            deadline: None
+        }
+    }
+}
+
+/// Alternates between assigning `datapoints` to the nearest of the `centroids` and recentering
+/// them, until a round goes by in which no point changes cluster, no centroid moves `stop.tol` or
+/// more, `stop.max_iter` rounds have been run, or `stop.deadline` has passed.
+///
+/// While iterating, clusters only hold indices into `datapoints`; the points themselves are cloned
+/// into `Cluster`s once at the end.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _lloyd<P, M>(centroids: Vec<P>, datapoints: &[P], stop: StoppingCriteria, metric: &M) -> KMeansResult<P>
+    where P: DataPoint, M: Metric<P>
+{
+    _lloyd_with_center(centroids, datapoints, stop, metric, P::mean_refs)
+}
+
+/// `_lloyd`, but recentering each cluster on `center` of its points instead of their mean
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _lloyd_with_center<P, M>(centroids: Vec<P>, datapoints: &[P], stop: StoppingCriteria, metric: &M, center: fn(&[&P]) -> Option<P>) -> KMeansResult<P>
+    where P: DataPoint, M: Metric<P>
+{
+    let (clusters, labels, iterations, converged) = _lloyd_indices(centroids, datapoints, stop, metric, center);
+    let inertia = _intermediate_inertia(&clusters, datapoints, &Euclidean);
+    let clusters = clusters.iter()
+        .map(|c| Cluster::from_intermediate(c, datapoints))
+        .collect();
+    KMeansResult { clusters, labels, iterations, converged, inertia, inertia_history: vec![] }
+}
+
+/// `_lloyd_with_center`, stopping short of cloning the points into `Cluster`s. Returns the
+/// clusters, the labels, the number of rounds run and whether it converged.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _lloyd_indices<P, M>(centroids: Vec<P>, datapoints: &[P], stop: StoppingCriteria, metric: &M, center: fn(&[&P]) -> Option<P>) -> (Vec<IntermediateCluster<P>>, Vec<usize>, usize, bool)
+    where P: DataPoint, M: Metric<P>
+{
+    _lloyd_observed(centroids, datapoints, stop, metric, center, &mut |_, _| {})
+}
+
+/// `_lloyd_indices`, calling `observe` with the number of rounds run so far and the clusters at
+/// the end of each round.
+///
+/// Integer centroids rounded toward zero, or float rounding and ties, can leave the clustering
+/// going around in a cycle of states forever, most often going back and forth between two. Once a
+/// round ends with every point back in the cluster it was in some rounds before, the rest of the
+/// cycle is run through once more, as far as `stop.max_iter` allows, and whichever state along it
+/// has the lowest inertia is returned, as not converged. On that last lap, `observe` is shown the
+/// best state so far rather than the latest, so the last thing it sees is what's returned.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _lloyd_observed<P, M>(centroids: Vec<P>, datapoints: &[P], stop: StoppingCriteria, metric: &M, center: fn(&[&P]) -> Option<P>, observe: &mut dyn FnMut(usize, &[IntermediateCluster<P>])) -> (Vec<IntermediateCluster<P>>, Vec<usize>, usize, bool)
+    where P: DataPoint, M: Metric<P>
+{
+    let mut clusters: Vec<IntermediateCluster<P>> = centroids.into_iter()
+        .map(|centroid| IntermediateCluster { centroid, point_indices: vec![] })
+        .collect();
+
+    // Make the initial clusterings, remembering where each point went
+    let mut labels: Vec<usize> = datapoints.iter().enumerate()
+        .map(|(i, point)| _cluster(i, point, &mut clusters, metric))
+        .collect();
+    _fill_empty_clusters(&mut clusters, &mut labels, datapoints, metric);
+
+    // Rinse, repeat; until no point changes cluster
+    let mut iterations = 0;
+    let mut converged = false;
+    let mut cycle = CycleCheck::new(&labels);
+    // The clock is only checked once a round, so the deadline costs next to nothing
+    while iterations < stop.max_iter && !stop.past_deadline() {
+        let (changed, shift) = _lloyd_round(&mut clusters, &mut labels, datapoints, metric, center);
+        iterations += 1;
+
+        if !cycle.lapping() && (!changed || shift < stop.tol) {
+            observe(iterations, &clusters);
+            converged = true;
+            break;
+        }
+
+        let lapped = cycle.check(&labels, || _intermediate_inertia(&clusters, datapoints, metric), || (clusters.clone(), labels.clone()));
+        observe(iterations, cycle.best().map_or(&clusters, |(best, _)| best));
+        if lapped {
+            break;
+        }
+    }
+    if let Some(best) = cycle.into_best() {
+        (clusters, labels) = best;
+    }
+
+    (clusters, labels, iterations, converged)
+}
+
+/// Watches the labels a clustering loop ends each round with, for the clustering going around in a
+/// cycle of states instead of settling, using [Brent's] algorithm. `L` is the labels and `S` is
+/// whatever the loop needs to get a state back.
+///
+/// Once a round ends with every point where it was some rounds before, the loop should go around
+/// the cycle once more, and `check` keeps the state with the lowest inertia along it. That's the
+/// state to finish on, as not converged.
+///
+/// [Brent's]: https://en.wikipedia.org/wiki/Cycle_detection#Brent's_algorithm
+#[cfg(any(feature = "std", feature = "alloc", feature = "heapless"))]
+pub(crate) struct CycleCheck<L, S> {
+    /// Where every point was at the start of a stretch of rounds, each stretch twice as long as
+    /// the last, so any cycle is caught within a few laps
+    checkpoint: L,
+    stretch: usize,
+    since_checkpoint: usize,
+    /// How many rounds are left of the last lap around the cycle, once one has been found
+    lap: Option<usize>,
+    best: Option<(S, f64)>
+}
+
+#[cfg(any(feature = "std", feature = "alloc", feature = "heapless"))]
+impl<L: Clone + PartialEq, S> CycleCheck<L, S> {
+    pub(crate) fn new(labels: &L) -> Self {
+        Self { checkpoint: labels.clone(), stretch: 1, since_checkpoint: 0, lap: None, best: None }
+    }
+
+    /// Whether a cycle has been found and the loop is going around it once more, so shouldn't
+    /// stop just because a round changed nothing
+    pub(crate) fn lapping(&self) -> bool {
+        self.lap.is_some()
+    }
+
+    /// Notes the `labels` a round that didn't converge ended with. `inertia` is only called once a
+    /// cycle has been found, and `state` only for a new best. Returns whether the last lap is done,
+    /// and the loop should stop.
+    pub(crate) fn check(&mut self, labels: &L, inertia: impl FnOnce() -> f64, state: impl FnOnce() -> S) -> bool {
+        if let Some(remaining) = self.lap {
+            let inertia = inertia();
+            if self.best.as_ref().is_none_or(|(_, best)| inertia < *best) {
+                self.best = Some((state(), inertia));
+            }
+            self.lap = Some(remaining - 1);
+            return remaining == 1;
+        }
+
+        self.since_checkpoint += 1;
+        if *labels == self.checkpoint {
+            self.best = Some((state(), inertia()));
+            self.lap = Some(self.since_checkpoint - 1);
+            return self.since_checkpoint == 1;
+        }
+        if self.since_checkpoint == self.stretch {
+            self.checkpoint.clone_from(labels);
+            self.stretch *= 2;
+            self.since_checkpoint = 0;
+        }
+        false
+    }
+
+    /// The state with the lowest inertia along the cycle so far, once one has been found
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    pub(crate) fn best(&self) -> Option<&S> {
+        self.best.as_ref().map(|(state, _)| state)
+    }
+
+    pub(crate) fn into_best(self) -> Option<S> {
+        self.best.map(|(state, _)| state)
+    }
+}
+
+/// Runs one round of `_lloyd`, recentering each cluster on `center` of its points and then
+/// reassigning every point. Returns whether any point changed cluster, and how far the
+/// farthest-moving centroid went.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _lloyd_round<P, M>(clusters: &mut [IntermediateCluster<P>], labels: &mut [usize], datapoints: &[P], metric: &M, center: fn(&[&P]) -> Option<P>) -> (bool, f64)
+    where P: DataPoint, M: Metric<P>
+{
+    // Recalculate the means based on the points in the cluster, keeping track of how far the
+    // farthest-moving one went
+    let mut shift = 0.0f64;
+    for cluster in clusters.iter_mut() {
+        let old_centroid = cluster.recenter(datapoints, center);
+        shift = shift.max(metric.distance(&old_centroid, &cluster.centroid));
+        cluster.point_indices.clear();
+    }
+    // Cluster again, noting whether anything moved. Refilling an empty cluster can put a point
+    // right back where it was, so that's only a change if it ends up somewhere new.
+    let previous = labels.to_vec();
+    for (i, (label, point)) in labels.iter_mut().zip(datapoints.iter()).enumerate() {
+        *label = _cluster(i, point, clusters, metric);
+    }
+    _fill_empty_clusters(clusters, labels, datapoints, metric);
+    (*labels != previous[..], shift)
+}
+
+/// The sum of the squared distances from each point to the centroid of its cluster, under `metric`
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _intermediate_inertia<P: DataPoint, M: Metric<P>>(clusters: &[IntermediateCluster<P>], datapoints: &[P], metric: &M) -> f64 {
+    clusters.iter()
+        .flat_map(|c| c.point_indices.iter().map(move |i| metric.distance_sq(&datapoints[*i], &c.centroid)))
+        .sum()
+}
+
+/// Moves a point into each cluster that has none, so that no centroid is left to be recalculated
+/// from nothing. The point taken is the one farthest from its own centroid, out of the clusters
+/// that have more than one point, and the empty cluster's centroid is moved onto it.
+///
+/// Returns the indices of the points that were moved.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _fill_empty_clusters<P, M>(clusters: &mut [IntermediateCluster<P>], labels: &mut [usize], datapoints: &[P], metric: &M) -> Vec<usize>
+    where P: DataPoint, M: Metric<P>
+{
+    let mut moved = vec![];
+    for empty in 0..clusters.len() {
+        if !clusters[empty].point_indices.is_empty() {
+            continue;
+        }
+
+        let mut farthest = None;
+        let mut farthest_distance = f64::NEG_INFINITY;
+        for (i, point) in datapoints.iter().enumerate() {
+            let cluster = &clusters[labels[i]];
+            if cluster.point_indices.len() < 2 {
+                continue;
+            }
+
+            let distance = metric.distance(&cluster.centroid, point);
+            if distance > farthest_distance {
+                farthest = Some(i);
+                farthest_distance = distance;
+            }
+        }
+
+        // Fewer points than clusters, so some have to stay empty
+        let i = match farthest {
+            Some(i) => i,
+            None => break
+        };
+        clusters[labels[i]].point_indices.retain(|j| *j != i);
+        clusters[empty].point_indices.push(i);
+        clusters[empty].centroid = datapoints[i].clone();
+        labels[i] = empty;
+        moved.push(i);
+    }
+
+    moved
+}
+
+/// Initializes the clusters using the chosen `init` method
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _initialize_clusters<P, M, R>(k: usize, datapoints: &[P], init: InitMethod, metric: &M, rng: &mut R) -> Vec<Cluster<P>>
+    where P: Clone, M: Metric<P>, R: Rng + ?Sized
+{
+    match init {
+        InitMethod::KMeansPlusPlus => _kmeans_plus_plus(k, datapoints, metric, rng),
+        InitMethod::Forgy => _forgy(k, datapoints, rng),
+        InitMethod::KMeansParallel { l, rounds } => _kmeans_parallel(k, datapoints, l, rounds, metric, rng),
+        InitMethod::GreedyKMeansPlusPlus => _greedy_kmeans_plus_plus(k, datapoints, metric, rng)
+    }
+}
+
+/// Initializes the clusters by picking `k` distinct points uniformly at random
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _forgy<P: Clone, R: Rng + ?Sized>(k: usize, datapoints: &[P], rng: &mut R) -> Vec<Cluster<P>> {
+    datapoints.choose_multiple(rng, k).map(Cluster::new).collect()
+}
+
+/// Initializes the clusters using an initialization algorithm based on [k-means++].
+///
+/// [k-means++]: https://en.wikipedia.org/wiki/K-means%2B%2B#Improved_initialization_algorithm
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _kmeans_plus_plus<P, M, R>(k: usize, datapoints: &[P], metric: &M, rng: &mut R) -> Vec<Cluster<P>>
+    where P: Clone, M: Metric<P>, R: Rng + ?Sized
+{
+    let mut datapoints: Vec<&P> = datapoints.iter().collect();
+    let mut clusters = Vec::with_capacity(k);
+    // First centroid is selected with a uniform distribution
+    let first_point = datapoints.remove(rng.gen_range(0..datapoints.len()));
+    clusters.push(Cluster::new(first_point));
+    let mut distribution: Vec<f64> = datapoints.iter()
+        .map(|p| shortest_center_distance(&[first_point], p, metric))
+        .collect();
+
+    // Keep selecting unique points until we have `k` centroids
+    while clusters.len() < k {
+        let point = datapoints.remove(select_point(&distribution, rng));
+        clusters.push(Cluster::new(point));
+        let centroids = Cluster::centroids_ref(&clusters);
+        distribution = datapoints.iter()
+            .map(|p| shortest_center_distance(&centroids, p, metric))
+            .collect()
+    }
+
+    clusters
+}
+
+/// `_kmeans_plus_plus`, but drawing `2 + ln k` candidates for each centroid after the first and
+/// keeping the one that leaves the least total squared distance from each point to its nearest
+/// centroid. With fewer points than `k`, every point becomes a centroid.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _greedy_kmeans_plus_plus<P, M, R>(k: usize, datapoints: &[P], metric: &M, rng: &mut R) -> Vec<Cluster<P>>
+    where P: Clone, M: Metric<P>, R: Rng + ?Sized
+{
+    let trials = 2 + (k as f64).ln() as usize;
+    let mut datapoints: Vec<&P> = datapoints.iter().collect();
+    let mut clusters = Vec::with_capacity(k);
+    let first_point = datapoints.remove(rng.gen_range(0..datapoints.len()));
+    clusters.push(Cluster::new(first_point));
+    let mut distribution: Vec<f64> = datapoints.iter()
+        .map(|p| shortest_center_distance(&[first_point], p, metric))
+        .collect();
+
+    // Once every point is a centroid, there are no candidates left to draw
+    while clusters.len() < k && !datapoints.is_empty() {
+        // The candidate, and each point's squared distance to its nearest centroid with it added
+        let mut best: Option<(usize, f64, Vec<f64>)> = None;
+        for _ in 0..trials {
+            let candidate = select_point(&distribution, rng);
+            let updated: Vec<f64> = datapoints.iter().zip(distribution.iter())
+                .map(|(p, d)| d.min(shortest_center_distance(&[datapoints[candidate]], p, metric)))
+                .collect();
+            let potential = updated.iter().fold(0.0, f64::add);
+            if best.as_ref().is_none_or(|(_, best_potential, _)| potential < *best_potential) {
+                best = Some((candidate, potential, updated));
+            }
+        }
+
+        let (candidate, _, mut updated) = best.unwrap_or_else(|| unreachable!());
+        updated.remove(candidate);
+        clusters.push(Cluster::new(datapoints.remove(candidate)));
+        distribution = updated;
+    }
+
+    clusters
+}
+
+/// Returns the squared distance from `point` to whichever of the `centers` is closest.
+///
+/// NaN distances (from a NaN coordinate, say) are skipped. If every distance is NaN, this is
+/// `0.0`, so that a point that can't be measured is never picked as a new center rather than
+/// poisoning the whole distribution.
+#[cfg(any(feature = "std", feature = "alloc", feature = "heapless"))]
+fn shortest_center_distance<P, M: Metric<P>>(centers: &[&P], point: &P, metric: &M) -> f64 {
+    centers.iter()
+        // calculate the squared distances between each center and `point`
+        .map(|c| metric.distance_sq(c, point))
+        .filter(|d| !d.is_nan())
+        // take the minimum of those distances
+        .reduce(f64::min)
+        .unwrap_or(0.0)
+}
+
+/// Selects the index of a point at random, with each point's chance of being picked proportional
+/// to its weight in `distribution`. Points with a weight of zero are never picked, unless every
+/// point's weight is zero, in which case they're all equally likely.
+#[cfg(any(feature = "std", feature = "alloc", feature = "heapless"))]
+fn select_point<R: Rng + ?Sized>(distribution: &[f64], rng: &mut R) -> usize {
+    let sum: f64 = distribution.iter().fold(0.0, |sum, weight| sum + weight);
+    if sum <= 0.0 {
+        return rng.gen_range(0..distribution.len());
+    }
+
+    // Each point's selection criterion is the running total of the weights up to and including
+    // it. We generate a random number and select the first point whose selection criterion is
+    // greater than that number.
+    // Like throwing a dart at a number line and seeing what range of values it falls in.
+    let rn: f64 = rng.gen_range(0.0..sum);
+    let mut criterion = 0.0;
+    for (i, weight) in distribution.iter().enumerate() {
+        criterion += weight;
+        if criterion > rn {
+            return i;
+        }
+    }
+    // Only reachable if rounding leaves the running total a hair short of `sum`, in which case the
+    // dart landed at the very end of the line
+    distribution.iter().rposition(|weight| *weight > 0.0).unwrap_or(distribution.len() - 1)
+}
+
+/// Initializes the clusters using [k-means||].
+///
+/// One point is picked uniformly at random, then over `rounds` rounds every point is picked
+/// independently with probability `l` times its share of the total squared distance to the
+/// candidates picked so far, so that around `l` candidates are added per round. Each candidate is
+/// weighted by how many points are nearest to it, and k-means++ on the weighted candidates picks
+/// the final `k`.
+///
+/// [k-means||]: https://arxiv.org/abs/1203.6402
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _kmeans_parallel<P, M, R>(k: usize, datapoints: &[P], l: f64, rounds: usize, metric: &M, rng: &mut R) -> Vec<Cluster<P>>
+    where P: Clone, M: Metric<P>, R: Rng + ?Sized
+{
+    let first = rng.gen_range(0..datapoints.len());
+    let mut candidates = vec![first];
+    let mut distribution: Vec<f64> = datapoints.iter()
+        .map(|p| shortest_center_distance(&[&datapoints[first]], p, metric))
+        .collect();
+
+    // Oversample. Points that are already candidates are at distance zero, so are never picked again.
+    for _ in 0..rounds {
+        let cost = distribution.iter().fold(0.0, f64::add);
+        if cost == 0.0 {
+            break;
+        }
+
+        let picked: Vec<usize> = (0..datapoints.len())
+            .filter(|i| rng.gen::<f64>() < l * distribution[*i] / cost)
+            .collect();
+        let centers: Vec<&P> = picked.iter().map(|i| &datapoints[*i]).collect();
+        for (d, p) in distribution.iter_mut().zip(datapoints.iter()) {
+            *d = d.min(shortest_center_distance(&centers, p, metric));
+        }
+        candidates.extend(picked);
+    }
+
+    // Too few candidates to choose from, so top them up the k-means++ way
+    while candidates.len() < k {
+        let others: Vec<usize> = (0..datapoints.len()).filter(|i| !candidates.contains(i)).collect();
+        let others_distribution: Vec<f64> = others.iter().map(|i| distribution[*i]).collect();
+        let point = others[select_point(&others_distribution, rng)];
+        for (d, p) in distribution.iter_mut().zip(datapoints.iter()) {
+            *d = d.min(shortest_center_distance(&[&datapoints[point]], p, metric));
+        }
+        candidates.push(point);
+    }
+
+    // Weight each candidate by the number of points nearest to it
+    let mut weights = vec![0.0; candidates.len()];
+    for point in datapoints.iter() {
+        weights[_nearest_by(candidates.iter().map(|i| &datapoints[*i]), point, metric, |_, _| false)] += 1.0;
+    }
+
+    let candidates = candidates.iter().map(|i| &datapoints[*i]).collect();
+    _weighted_kmeans_plus_plus(k, candidates, weights, metric, rng)
+}
+
+/// Initializes the clusters with k-means++, treating each of the `points` as if it were there
+/// `weights[i]` times
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _weighted_kmeans_plus_plus<P, M, R>(k: usize, mut points: Vec<&P>, mut weights: Vec<f64>, metric: &M, rng: &mut R) -> Vec<Cluster<P>>
+    where P: Clone, M: Metric<P>, R: Rng + ?Sized
+{
+    let mut clusters = Vec::with_capacity(k);
+    let mut distribution = weights.clone();
+    while clusters.len() < k {
+        let chosen = select_point(&distribution, rng);
+        clusters.push(Cluster::new(points.swap_remove(chosen)));
+        weights.swap_remove(chosen);
+
+        let centroids = Cluster::centroids_ref(&clusters);
+        distribution = points.iter().zip(weights.iter())
+            .map(|(p, w)| w * shortest_center_distance(&centroids, p, metric))
+            .collect();
+    }
+
+    clusters
+}
+
+/// Returns the index of the cluster whose centroid is closest to `point`, by exactly the rule the
+/// clustering itself uses to assign points.
+///
+/// Distances are compared by `DataPoint::dist_sq`. On a tie, the smallest centroid by
+/// `DataPoint::tie_order` wins, then the cluster that comes first in `clusters`, and a centroid
+/// whose distance is NaN is never chosen over one whose isn't. If `clusters` is empty, there's
+/// nothing to assign it to, and this is `None`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn assign<P: DataPoint>(point: &P, clusters: &[Cluster<P>]) -> Option<usize> {
+    if clusters.is_empty() {
+        return None;
+    }
+
+    Some(_nearest(clusters.iter().map(|c| &c.centroid), point, &Euclidean))
+}
+
+/// Returns the index of the cluster whose centroid is closest to `point`, as `assign` does.
+///
+/// Ties are broken the same way too, and it's `None` if `clusters` is empty.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn predict<P: DataPoint>(clusters: &[Cluster<P>], point: &P) -> Option<usize> {
+    assign(point, clusters)
+}
+
+/// Returns the index of the closest cluster for each of the `points`, in order, or `None` if
+/// `clusters` is empty
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn predict_batch<P: DataPoint>(clusters: &[Cluster<P>], points: &[P]) -> Option<Vec<usize>> {
+    // With no points, nothing would notice there are no clusters either
+    if clusters.is_empty() {
+        return None;
+    }
+
+    points.iter().map(|p| predict(clusters, p)).collect()
+}
+
+/// Returns the index of the cluster whose centroid is closest to `point`, as `predict` does, along
+/// with the distance to that centroid. A point that's much farther from its centroid than the
+/// cluster's own points are may not belong to any of the clusters.
+///
+/// If `clusters` is empty, this is `None`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn predict_with_distance<P: DataPoint>(clusters: &[Cluster<P>], point: &P) -> Option<(usize, f64)> {
+    let label = predict(clusters, point)?;
+    Some((label, point.dist(&clusters[label].centroid)))
+}
+
+/// Returns the index of the closest cluster for each of the `points`, and the distance to its
+/// centroid, in order, or `None` if `clusters` is empty
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn predict_batch_with_distance<P: DataPoint>(clusters: &[Cluster<P>], points: &[P]) -> Option<Vec<(usize, f64)>> {
+    if clusters.is_empty() {
+        return None;
+    }
+
+    points.iter().map(|p| predict_with_distance(clusters, p)).collect()
+}
+
+/// Returns the distance from each of the `points` to each of the `clusters`' centroids, so that
+/// row `i` column `j` is `points[i].dist(&clusters[j].centroid)`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn transform<P: DataPoint>(clusters: &[Cluster<P>], points: &[P]) -> Vec<Vec<f64>> {
+    points.iter()
+        .map(|p| clusters.iter().map(|c| p.dist(&c.centroid)).collect())
+        .collect()
+}
+
+/// Lists each of the `clusters`' centroid and size, one per line, numbered by their index, like
+///
+/// ```text
+/// 0: Cluster(centroid=(3.0, 3.0), n=18)
+/// 1: Cluster(centroid=(8.0, 6.0), n=15)
+/// ```
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn summary<P: Debug>(clusters: &[Cluster<P>]) -> String {
+    let mut summary = String::new();
+    for (i, cluster) in clusters.iter().enumerate() {
+        // Writing to a `String` never fails
+        let _ = writeln!(summary, "{}: {}", i, cluster);
+    }
+    summary
+}
+
+/// The sum of the inertias of all the `clusters`, which is the quantity k-means tries to minimize
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn total_inertia<P: DataPoint>(clusters: &[Cluster<P>]) -> f64 {
+    clusters.iter().map(Cluster::inertia).sum()
+}
+
+/// The mean [silhouette coefficient] of all the points in the `clusters`, from -1 to 1. Higher is
+/// better, so comparing this across values of k is a way to pick one.
+///
+/// For each point, `a` is its mean distance to the rest of its own cluster and `b` is its mean
+/// distance to the points of the nearest other cluster, and its coefficient is
+/// `(b - a) / max(a, b)`. Points alone in their cluster count as 0, and so does everything if
+/// there are fewer than two clusters with points in them.
+///
+/// Every pair of points is measured, so this takes `O(n^2)` time.
+///
+/// [silhouette coefficient]: https://en.wikipedia.org/wiki/Silhouette_(clustering)
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn silhouette_score<P: DataPoint>(clusters: &[Cluster<P>]) -> f64 {
+    let total_distance = |point: &P, cluster: &Cluster<P>| -> f64 {
+        cluster.points.iter().map(|q| point.dist(q)).sum()
+    };
+
+    let mut sum = 0.0;
+    let mut n = 0;
+    for (c, cluster) in clusters.iter().enumerate() {
+        n += cluster.points.len();
+        if cluster.points.len() < 2 {
+            continue;
+        }
+
+        for point in cluster.points.iter() {
+            // The point is zero away from itself, so it only has to be left out of the count
+            let a = total_distance(point, cluster) / (cluster.points.len() - 1) as f64;
+            let b = clusters.iter().enumerate()
+                .filter(|(o, other)| *o != c && !other.points.is_empty())
+                .map(|(_, other)| total_distance(point, other) / other.points.len() as f64)
+                .fold(f64::INFINITY, f64::min);
+
+            let scale = a.max(b);
+            if b.is_finite() && scale > 0.0 {
+                sum += (b - a) / scale;
+            }
+        }
+    }
+
+    if n == 0 {
+        return 0.0;
+    }
+    sum / n as f64
+}
+
+/// The [Davies-Bouldin index] of the `clusters`. Lower is better, with 0 meaning every cluster is
+/// a single spot.
+///
+/// A cluster's scatter is the mean distance from its points to its centroid. Each cluster is
+/// compared against whichever other cluster it's most easily confused with, by the ratio of their
+/// combined scatter to the distance between their centroids, and those ratios are averaged.
+/// Clusters sharing a centroid can't be told apart at all, so they make the index infinite.
+/// Empty clusters are left out, and with fewer than two clusters left, the index is 0.
+///
+/// [Davies-Bouldin index]: https://en.wikipedia.org/wiki/Davies%E2%80%93Bouldin_index
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn davies_bouldin_index<P: DataPoint>(clusters: &[Cluster<P>]) -> f64 {
+    let clusters: Vec<&Cluster<P>> = clusters.iter().filter(|c| !c.points.is_empty()).collect();
+    if clusters.len() < 2 {
+        return 0.0;
+    }
+
+    let scatters: Vec<f64> = clusters.iter()
+        .map(|c| c.points.iter().map(|p| c.centroid.dist(p)).sum::<f64>() / c.points.len() as f64)
+        .collect();
+
+    let mut sum = 0.0;
+    for (i, cluster) in clusters.iter().enumerate() {
+        let worst = clusters.iter().enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(j, other)| {
+                let distance = cluster.centroid.dist(&other.centroid);
+                if distance == 0.0 {
+                    f64::INFINITY
+                } else {
+                    (scatters[i] + scatters[j]) / distance
+                }
+            })
+            .fold(0.0, f64::max);
+        sum += worst;
+    }
+
+    sum / clusters.len() as f64
+}
+
+/// The [Bayesian information criterion] of the `clusters`, as used by `xmeans`. Higher is better.
+///
+/// The clusters are taken as a mixture of spherical Gaussians, one around each centroid, all with
+/// the same variance, each weighted by its share of the points. The BIC is the log-likelihood of
+/// the points under that model, less half the log of the number of points for each of the model's
+/// parameters: `k - 1` weights, `k` centroids of `d` coordinates each, and the variance. So more
+/// clusters have to fit the points enough better to be worth it.
+///
+/// The variance is estimated as `total_inertia / (n - k)`, so with no more points than clusters
+/// the BIC is negative infinity. If every point is on its centroid, it's infinite. Empty clusters
+/// count towards k, but add nothing to the likelihood.
+///
+/// [Bayesian information criterion]: https://en.wikipedia.org/wiki/Bayesian_information_criterion
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn bic<P: DataPoint + Coordinates>(clusters: &[Cluster<P>]) -> f64 {
+    let n: usize = clusters.iter().map(Cluster::size).sum();
+    let (log_likelihood, parameters) = _spherical_gaussian_fit(clusters);
+    log_likelihood - parameters / 2.0 * (n as f64).ln()
+}
+
+/// The [Akaike information criterion] of the `clusters`. Higher is better, as with `bic`.
+///
+/// This is the same log-likelihood as `bic`, less one for each of the model's parameters rather
+/// than half the log of the number of points. With more than seven points, that's a lighter
+/// penalty, so the AIC tends to favor more clusters than the BIC does.
+///
+/// [Akaike information criterion]: https://en.wikipedia.org/wiki/Akaike_information_criterion
+#[cfg(any(feature = "std", feature = "alloc"))]
+pub fn aic<P: DataPoint + Coordinates>(clusters: &[Cluster<P>]) -> f64 {
+    let (log_likelihood, parameters) = _spherical_gaussian_fit(clusters);
+    log_likelihood - parameters
+}
+
+/// The log-likelihood of the points in `clusters` under the mixture of spherical Gaussians
+/// described for `bic`, and the number of parameters that mixture has
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _spherical_gaussian_fit<P: DataPoint + Coordinates>(clusters: &[Cluster<P>]) -> (f64, f64) {
+    let n: usize = clusters.iter().map(Cluster::size).sum();
+    let k = clusters.len();
+    let dimensions = clusters.iter().find_map(|c| c.points.first()).map_or(0, Coordinates::dimensions) as f64;
+    let parameters = (k as f64 - 1.0) + k as f64 * dimensions + 1.0;
+    if n <= k {
+        return (f64::NEG_INFINITY, parameters);
+    }
+
+    let (n, k) = (n as f64, k as f64);
+    let variance = total_inertia(clusters) / (n - k);
+    // Each point's log-likelihood is the log of its cluster's weight plus its log-density under
+    // the cluster's Gaussian. The squared distances in those densities add up to the total
+    // inertia, which is `(n - k)` variances.
+    let weights: f64 = clusters.iter()
+        .filter(|c| !c.is_empty())
+        .map(|c| c.size() as f64 * (c.size() as f64 / n).ln())
+        .sum();
+    let log_likelihood = weights - n * dimensions / 2.0 * (2.0 * core::f64::consts::PI * variance).ln() - (n - k) / 2.0;
+    (log_likelihood, parameters)
+}
+
+/// Returns the index of whichever of the `centroids` is closest to `point` under `metric`. If it's
+/// exactly as close to more than one, it goes to the smallest of them by `DataPoint::tie_order`,
+/// then to the first.
+fn _nearest<'a, P, M, I>(centroids: I, point: &P, metric: &M) -> usize
+    where P: 'a + DataPoint, M: Metric<P>, I: IntoIterator<Item = &'a P>
+{
+    _nearest_by(centroids, point, metric, |centroid, best| centroid.tie_order(best) == core::cmp::Ordering::Less)
+}
+
+/// `_nearest`, for points that aren't necessarily `DataPoint`s, where a centroid exactly as close
+/// as the best so far only takes its place if `wins_tie(centroid, best)`
+fn _nearest_by<'a, P, M, I, F>(centroids: I, point: &P, metric: &M, wins_tie: F) -> usize
+    where P: 'a, M: Metric<P>, I: IntoIterator<Item = &'a P>, F: Fn(&P, &P) -> bool
+{
+    let mut closest_cluster = 0;
+    let mut closest_centroid: Option<&P> = None;
+    let mut closest_distance = f64::INFINITY;
+    for (c, centroid) in centroids.into_iter().enumerate() {
+        let distance = metric.distance_sq(point, centroid);
+        let tied = distance == closest_distance && closest_centroid.is_some_and(|best| wins_tie(centroid, best));
+        if distance < closest_distance || tied {
+            closest_cluster = c;
+            closest_centroid = Some(centroid);
+            closest_distance = distance;
+        }
+    }
+    closest_cluster
+}
+
+/// How much is added to the diagonal of a covariance matrix before it's inverted, so that points
+/// all on one line or plane, or all in one place, still have an inverse covariance. It's in the
+/// squared units of the coordinates.
+#[cfg(any(feature = "std", feature = "alloc"))]
+const COVARIANCE_REGULARIZATION: f64 = 1e-6;
+
+/// Each of the `points` less their mean, as a vector of coordinates, along with their (population)
+/// covariance matrix. With no points, both are empty.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _centered_covariance<P: Coordinates>(points: &[P]) -> (Vec<Vec<f64>>, Vec<Vec<f64>>) {
+    let dimensions = match points.first() {
+        Some(p) => p.dimensions(),
+        None => return (vec![], vec![])
+    };
+    let n = points.len() as f64;
+    let mean: Vec<f64> = (0..dimensions)
+        .map(|j| points.iter().fold(CompensatedSum::default(), |sum, p| sum + p.coordinate(j)).total() / n)
+        .collect();
+    let centered: Vec<Vec<f64>> = points.iter()
+        .map(|p| (0..dimensions).map(|j| p.coordinate(j) - mean[j]).collect())
+        .collect();
+
+    let mut covariance = vec![vec![0.0; dimensions]; dimensions];
+    for x in centered.iter() {
+        for (row, x_j) in covariance.iter_mut().zip(x.iter()) {
+            for (entry, x_l) in row.iter_mut().zip(x.iter()) {
+                *entry += x_j * x_l / n;
+            }
+        }
+    }
+    (centered, covariance)
+}
+
+/// The inverse of the covariance matrix of `points`, after adding `COVARIANCE_REGULARIZATION` to
+/// its diagonal, along with the log of the regularized matrix's determinant. `None` if there are
+/// no points or a coordinate is NaN.
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _inverse_covariance<P: Coordinates>(points: &[P]) -> Option<(Vec<Vec<f64>>, f64)> {
+    let (_, mut matrix) = _centered_covariance(points);
+    if matrix.is_empty() {
+        return None;
+    }
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row[i] += COVARIANCE_REGULARIZATION;
+    }
+
+    // Gauss-Jordan elimination, turning `matrix` into the identity and the identity into the
+    // inverse. The matrix is positive definite, so it needs no pivoting, every pivot is positive,
+    // and the determinant is their product.
+    let d = matrix.len();
+    let mut inverse: Vec<Vec<f64>> = (0..d).map(|i| (0..d).map(|j| if i == j { 1.0 } else { 0.0 }).collect()).collect();
+    let mut log_det = 0.0;
+    for col in 0..d {
+        let pivot = matrix[col][col];
+        if pivot.is_nan() || pivot <= 0.0 {
+            return None;
+        }
+        log_det += pivot.ln();
+
+        for j in 0..d {
+            matrix[col][j] /= pivot;
+            inverse[col][j] /= pivot;
+        }
+        for row in 0..d {
+            let factor = matrix[row][col];
+            if row == col || factor == 0.0 {
+                continue;
+            }
+            for j in 0..d {
+                matrix[row][j] -= factor * matrix[col][j];
+                inverse[row][j] -= factor * inverse[col][j];
+            }
+        }
+    }
+    Some((inverse, log_det))
+}
+
+/// Assigns the point at index `i` to the cluster whose centroid is closest, returning that
+/// cluster's index
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn _cluster<P: DataPoint, M: Metric<P>>(i: usize, p: &P, clusters: &mut [IntermediateCluster<P>], metric: &M) -> usize {
+    let closest_cluster = _nearest(clusters.iter().map(|c| &c.centroid), p, metric);
+    clusters[closest_cluster].point_indices.push(i);
+    closest_cluster
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::{string::ToString, vec::Vec};
+
+    use crate::prelude::*;
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn float_clustering() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ];
+
+        let clusters = kmeans(2, points);
+        let centroids = Cluster::centroids(&clusters);
+
+        assert!(centroids.contains(&(71.0/9.0, 113.0/18.0)));
+        assert!(centroids.contains(&(46.0/15.0, 13.0/5.0)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ];
+
+        let clusters = kmeans(2, points);
+        let json = serde_json::to_string(&clusters).unwrap();
+        let loaded: Vec<Cluster<(f64, f64)>> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(Cluster::centroids(&loaded), Cluster::centroids(&clusters));
+        assert_eq!(loaded, clusters);
+        assert_eq!(predict(&loaded, &(0.0, 0.0)), predict(&clusters, &(0.0, 0.0)));
+    }
+
+    #[cfg(feature = "nalgebra")]
+    #[test]
+    fn nalgebra_points() {
+        use nalgebra::{Point2, Point3, Vector2};
+
+        let points = vec![
+            Point2::new(1.0, 2.0), Point2::new(1.0, 3.0), Point2::new(2.0, 2.0),
+            Point2::new(8.0, 4.0), Point2::new(8.0, 5.0), Point2::new(9.0, 6.0)
+        ];
+        let mut clusters = kmeans(2, points);
+        clusters.sort_by(|a, b| a.centroid.x.partial_cmp(&b.centroid.x).unwrap());
+        assert_eq!(clusters[0].points.len(), 3);
+        assert!((clusters[0].centroid - Point2::new(4.0 / 3.0, 7.0 / 3.0)).norm() < 1e-12);
+        assert!((clusters[1].centroid - Point2::new(25.0 / 3.0, 5.0)).norm() < 1e-12);
+
+        assert_eq!(Point3::new(0.0, 0.0, 0.0).dist(&Point3::new(2.0, 3.0, 6.0)), 7.0);
+        assert_eq!(Vector2::new(0.0, 0.0).dist_sq(&Vector2::new(3.0, 4.0)), 25.0);
+        assert_eq!(<Vector2<f64> as DataPoint>::mean(&[Vector2::new(1.0, 2.0), Vector2::new(3.0, 6.0)]), Some(Vector2::new(2.0, 4.0)));
+        assert_eq!(Point2::<f64>::mean(&[]), None);
+    }
+
+    #[cfg(feature = "num-complex")]
+    #[test]
+    fn complex_constellation() {
+        use num_complex::Complex;
+
+        // Noisy symbols of a 4-QAM constellation, one in each quadrant
+        let symbols = [Complex::new(1.0, 1.0), Complex::new(-1.0, 1.0), Complex::new(-1.0, -1.0), Complex::new(1.0, -1.0)];
+        let noise = [Complex::new(0.1, -0.05), Complex::new(-0.08, 0.12), Complex::new(0.03, 0.02)];
+        let points: Vec<Complex<f64>> = symbols.iter()
+            .flat_map(|s| noise.iter().map(move |n| s + n))
+            .collect();
+
+        for seed in 0..10 {
+            let mut clusters = kmeans_seeded(4, points.clone(), seed);
+            clusters.sort_by(|a, b| a.centroid.tie_order(&b.centroid));
+            let mut expected = symbols;
+            expected.sort_by(|a, b| a.tie_order(b));
+            for (cluster, symbol) in clusters.iter().zip(expected.iter()) {
+                assert_eq!(cluster.size(), 3);
+                assert!(cluster.centroid.dist(&(symbol + Complex::new(0.05 / 3.0, 0.03))) < 1e-12);
+            }
+        }
+
+        assert_eq!(Complex::new(0.0, 0.0).dist(&Complex::new(3.0, -4.0)), 5.0);
+        assert_eq!(Complex::<f64>::mean(&[]), None);
+    }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn glam_vectors() {
+        use glam::{Vec2, Vec3};
+
+        let points = vec![
+            Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 1.0),
+            Vec3::new(10.0, 10.0, 10.0), Vec3::new(11.0, 10.0, 9.0), Vec3::new(10.0, 12.0, 11.0)
+        ];
+        for seed in 0..10 {
+            let mut clusters = kmeans_seeded(2, points.clone(), seed);
+            clusters.sort_by(|a, b| a.centroid.tie_order(&b.centroid));
+            assert_eq!(clusters[0].points, points[..3]);
+            assert_eq!(clusters[1].points, points[3..]);
+            assert!(clusters[0].centroid.abs_diff_eq(Vec3::new(1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0), 1e-6));
+            assert!(clusters[1].centroid.abs_diff_eq(Vec3::new(31.0 / 3.0, 32.0 / 3.0, 10.0), 1e-5));
+        }
+
+        assert_eq!(Vec3::new(0.0, 0.0, 0.0).dist(&Vec3::new(2.0, 3.0, 6.0)), 7.0);
+        assert_eq!(Vec2::new(0.0, 0.0).dist_sq(&Vec2::new(3.0, 4.0)), 25.0);
+        assert_eq!(Vec2::mean(&[Vec2::new(1.0, 2.0), Vec2::new(3.0, 6.0)]), Some(Vec2::new(2.0, 4.0)));
+        assert_eq!(Vec3::mean(&[]), None);
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn ndarray_rows() {
+        use ndarray::{arr1, arr2};
+
+        let data = arr2(&[
+            [1.0, 2.0], [1.0, 3.0], [2.0, 2.0], [2.0, 3.0], [2.0, 4.0],
+            [3.0, 1.0], [3.0, 2.0], [3.0, 3.0], [3.0, 4.0], [4.0, 1.0], [4.0, 2.0], [4.0, 3.0],
+            [4.0, 4.0], [5.0, 2.0], [5.0, 3.0],
+            [6.0, 5.0], [6.0, 6.0], [6.0, 7.0], [7.0, 5.0], [7.0, 6.0], [7.0, 7.0], [7.0, 8.0],
+            [8.0, 4.0], [8.0, 5.0], [8.0, 6.0], [8.0, 7.0], [8.0, 8.0], [9.0, 5.0], [9.0, 6.0],
+            [9.0, 7.0], [9.0, 8.0], [10.0, 6.0], [10.0, 7.0]
+        ]);
+        let tuples: Vec<(f64, f64)> = data.rows().into_iter().map(|row| (row[0], row[1])).collect();
+
+        let mut clusters = kmeans_ndarray(2, data.view());
+        clusters.sort_by(|a, b| a.centroid[0].partial_cmp(&b.centroid[0]).unwrap());
+        let mut expected = kmeans(2, tuples);
+        expected.sort_by(|a, b| a.centroid.partial_cmp(&b.centroid).unwrap());
+
+        for (cluster, expected) in clusters.iter().zip(expected.iter()) {
+            assert!(cluster.centroid.dist(&arr1(&[expected.centroid.0, expected.centroid.1])) < 1e-12);
+            assert_eq!(cluster.points.len(), expected.points.len());
+        }
+        assert_eq!(arr1(&[0.0, 0.0]).dist(&arr1(&[3.0, 4.0])), 5.0);
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn int_clustering() {
+        let points = vec![
+            (1u8, 2u8), (1, 3), (2, 2), (2, 3), (2, 4),
+            (3, 1), (3, 2), (3, 3), (3, 4), (4, 1), (4, 2), (4, 3),
+            (4, 4), (5, 2), (5, 3),
+            (6, 5), (6, 6), (6, 7), (7, 5), (7, 6), (7, 7), (7, 8),
+            (8, 4), (8, 5), (8, 6), (8, 7), (8, 8), (9, 5), (9, 6),
+            (9, 7), (9, 8), (10, 6), (10, 7)
+        ];
+
+        let clusters = kmeans(2, points);
+        let centroids = Cluster::centroids(&clusters);
+
+        assert!(centroids.contains(&(7, 6)));
+        assert!(centroids.contains(&(3, 2)));
+    }
+
+    #[test]
+    fn bit_vectors() {
+        assert_eq!(0b1010u64.dist(&0b0110), 2.0);
+        assert_eq!(u64::mean(&[0b1100, 0b1010, 0b1001]), Some(0b1000));
+        // Each of the low two bits is set in exactly half, so neither wins
+        assert_eq!(u64::mean(&[0b01, 0b10]), Some(0));
+        assert_eq!(u64::mean(&[]), None);
+
+        // Mostly the high nibble, and mostly the low nibble, each with a bit or two flipped
+        let high = [0b1111_0000u64, 0b1110_0000, 0b1111_0001, 0b1011_0000, 0b0111_0100];
+        let low = [0b0000_1111u64, 0b0000_0111, 0b1000_1111, 0b0000_1101, 0b0010_1110];
+        let points: Vec<u64> = high.iter().chain(low.iter()).cloned().collect();
+
+        for seed in 0..10 {
+            let mut clusters = kmeans_seeded(2, points.clone(), seed);
+            clusters.sort_by_key(|c| c.centroid);
+            assert_eq!(Cluster::centroids(&clusters), vec![0b0000_1111, 0b1111_0000]);
+            assert_eq!(clusters[0].points, low);
+            assert_eq!(clusters[1].points, high);
+        }
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn color_quantization() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        // A noisy orange and a noisy teal, with the orange right up against the top of the range
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut pixels = vec![];
+        for _ in 0..50 {
+            pixels.push(Rgb(rng.gen_range(250..=255), rng.gen_range(120..140), rng.gen_range(0..10)));
+            pixels.push(Rgb(rng.gen_range(0..10), rng.gen_range(120..140), rng.gen_range(120..140)));
+        }
+
+        let mut palette = quantize(pixels.clone(), 2);
+        palette.sort_by_key(|c| c.centroid.0);
+        assert_eq!(palette.iter().map(Cluster::size).collect::<Vec<usize>>(), vec![50, 50]);
+        let (teal, orange) = (palette[0].centroid, palette[1].centroid);
+        assert!(teal.0 < 10 && (120..140).contains(&teal.1) && (120..140).contains(&teal.2));
+        assert!(orange.0 >= 250 && (120..140).contains(&orange.1) && orange.2 < 10);
+
+        assert_eq!(quantize(vec![Rgb(255, 255, 255); 4], 1)[0].centroid, Rgb(255, 255, 255));
+        assert_eq!(quantize(vec![Rgb(1, 2, 3), Rgb(4, 5, 6)], 16).len(), 2);
+        assert_eq!(Rgb::mean(&[Rgb(0, 0, 255), Rgb(255, 255, 0)]), Some(Rgb(127, 127, 127)));
+    }
+
+    #[test]
+    fn compensated_means() {
+        use crate::types::CompensatedSum;
+
+        // Every addition of 0.1 rounds a little, and adding them up one at a time lets it pile up
+        let points = vec![0.1f64; 1_000_000];
+        let naive = points.iter().fold(0.0, |acc, p| acc + p) / points.len() as f64;
+        assert_ne!(naive, 0.1);
+        assert_eq!(f64::mean(&points), Some(0.1));
+        assert_eq!(<[f64; 1]>::mean(&vec![[0.1]; 1_000_000]), Some([0.1]));
+        assert_eq!(Vec::<f64>::mean(&vec![vec![0.1, 0.2]; 1_000_000]), Some(vec![0.1, 0.2]));
+        assert_eq!(Vec::<f64>::weighted_mean(&vec![vec![0.1]; 1_000_000], &vec![2.0; 1_000_000]), vec![0.1]);
+
+        // The first 1 is too small to register next to 1e16 at all, but isn't lost
+        let points = [(1e16, 0f64), (1.0, 0.0), (-1e16, 0.0), (1.0, 0.0)];
+        assert_eq!(points.iter().fold(0.0, |acc, p| acc + p.0), 1.0);
+        assert_eq!(<(f64, f64)>::mean(&points), Some((0.5, 0.0)));
+        let reordered = [points[1], points[3], points[0], points[2]];
+        assert_eq!(<(f64, f64)>::mean(&reordered), <(f64, f64)>::mean(&points));
+
+        let halves = CompensatedSum::from(1e16) + 1.0 + (CompensatedSum::from(-1e16) + 1.0);
+        assert_eq!(halves.total(), 2.0);
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn standalone_initialization() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let points: Vec<(f64, f64)> = (0..20).map(|i| ((i % 5) as f64, (i / 5) as f64 * 10.0)).collect();
+        for seed in 0..10 {
+            let centroids = initialize_centroids_with_rng(4, &points, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(centroids.len(), 4);
+            for (i, centroid) in centroids.iter().enumerate() {
+                assert!(points.contains(centroid));
+                assert!(!centroids[..i].contains(centroid));
+            }
+
+            // The same seed gives the same start as the full run
+            let clusters = kmeans_seeded(4, points.clone(), seed);
+            let from_start = kmeans_from_centroids(centroids, points.clone()).unwrap();
+            assert_eq!(clusters, from_start);
+        }
+
+        assert_eq!(initialize_centroids(30, &points).len(), 20);
+        assert!(initialize_centroids(3, &Vec::<(f64, f64)>::new()).is_empty());
+        assert!(initialize_centroids(0, &points).is_empty());
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn float_centroids_for_int_points() {
+        let points = vec![
+            (1u8, 2u8), (1, 3), (2, 2), (2, 3), (2, 4),
+            (3, 1), (3, 2), (3, 3), (3, 4), (4, 1), (4, 2), (4, 3),
+            (4, 4), (5, 2), (5, 3),
+            (6, 5), (6, 6), (6, 7), (7, 5), (7, 6), (7, 7), (7, 8),
+            (8, 4), (8, 5), (8, 6), (8, 7), (8, 8), (9, 5), (9, 6),
+            (9, 7), (9, 8), (10, 6), (10, 7)
+        ];
+        let means = [(46.0 / 15.0, 13.0 / 5.0), (71.0 / 9.0, 113.0 / 18.0)];
+
+        // Rounded to integers, the centroids end up most of a unit away from the means
+        let mut rounded = Cluster::centroids(&kmeans(2, points.clone()));
+        rounded.sort();
+        for ((x, y), mean) in rounded.into_iter().zip(means.iter()) {
+            assert!((f64::from(x), f64::from(y)).dist(mean) > 0.5);
+        }
+
+        let mut clusters = kmeans_float_centroids(2, points.clone());
+        clusters.sort_by(|a, b| a.centroid.partial_cmp(&b.centroid).unwrap());
+        for (cluster, mean) in clusters.iter().zip(means.iter()) {
+            assert!(cluster.centroid.dist(mean) < 1e-12);
+        }
+        assert_eq!(clusters[0].points[0], (1.0, 2.0));
+
+        assert_eq!([3u8, 250].to_float(), [3.0, 250.0]);
+        assert_eq!((-1i32, 7i32, 0i32).to_float(), (-1.0, 7.0, 0.0));
+    }
+
+    #[test]
+    fn rounded_centroids() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use super::_rounded_centroids;
+
+        let points = vec![
+            (1u8, 2u8), (1, 3), (2, 2), (2, 3), (2, 4),
+            (3, 1), (3, 2), (3, 3), (3, 4), (4, 1), (4, 2), (4, 3),
+            (4, 4), (5, 2), (5, 3),
+            (6, 5), (6, 6), (6, 7), (7, 5), (7, 6), (7, 7), (7, 8),
+            (8, 4), (8, 5), (8, 6), (8, 7), (8, 8), (9, 5), (9, 6),
+            (9, 7), (9, 8), (10, 6), (10, 7)
+        ];
+        let floats: Vec<(f64, f64)> = points.iter().map(ToFloat::to_float).collect();
+
+        for seed in 0..10 {
+            let reference = kmeans_seeded(2, floats.clone(), seed);
+            let rounded = _rounded_centroids(2, points.clone(), &mut StdRng::seed_from_u64(seed));
+            for (rounded, reference) in rounded.iter().zip(reference.iter()) {
+                assert_eq!(rounded.centroid, <(u8, u8)>::from_float(&reference.centroid));
+                assert_eq!(rounded.points.iter().map(ToFloat::to_float).collect::<Vec<_>>(), reference.points);
+            }
+
+            // The means are (46/15, 13/5) and (71/9, 113/18)
+            let mut centroids = Cluster::centroids(&rounded);
+            centroids.sort();
+            assert_eq!(centroids, vec![(3, 3), (8, 6)]);
+        }
+
+        assert_eq!(<[u8; 3]>::from_float(&[2.5, -0.4, 300.0]), [3, 0, 255]);
+        assert_eq!(<(i32, i32)>::from_float(&(-2.6, 7.49)), (-3, 7));
+    }
+
+    #[test]
+    fn seeded_clustering_is_reproducible() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ];
+
+        let first = kmeans_seeded(3, points.clone(), 42);
+        let second = kmeans_seeded(3, points, 42);
+
+        assert_eq!(Cluster::centroids(&first), Cluster::centroids(&second));
+    }
+
+    #[test]
+    fn caller_supplied_rng() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let points = vec![
+            (1u8, 2u8), (1, 3), (2, 2), (2, 3), (2, 4),
+            (8, 4), (8, 5), (8, 6), (8, 7), (8, 8), (9, 5), (9, 6)
+        ];
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let clusters = kmeans_with_rng(2, points.clone(), &mut rng);
+
+        assert_eq!(Cluster::centroids(&clusters), Cluster::centroids(&kmeans_seeded(2, points, 7)));
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn forgy_initialization() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use super::_initialize_clusters;
+
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (9.0, 5.0), (9.0, 6.0)
+        ];
+
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let clusters = _initialize_clusters(4, &points, InitMethod::Forgy, &Euclidean, &mut rng);
+            let centroids = Cluster::centroids(&clusters);
+
+            assert_eq!(centroids.len(), 4);
+            for (i, centroid) in centroids.iter().enumerate() {
+                assert!(points.contains(centroid));
+                assert!(!centroids[..i].contains(centroid));
+            }
+        }
+
+        let clusters = kmeans_with_init(2, points.clone(), InitMethod::Forgy);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters.iter().map(|c| c.points.len()).sum::<usize>(), points.len());
+    }
+
+    #[test]
+    fn kmeans_parallel_initialization() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use super::{_initialize_clusters, _kmeans, StoppingCriteria};
+
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ];
+        let parallel = InitMethod::KMeansParallel { l: 8.0, rounds: 5 };
+
+        let (mut parallel_inertia, mut plus_plus_inertia) = (0.0, 0.0);
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let centroids = Cluster::centroids(&_initialize_clusters(4, &points, parallel, &Euclidean, &mut rng));
+            assert_eq!(centroids.len(), 4);
+            for (i, centroid) in centroids.iter().enumerate() {
+                assert!(points.contains(centroid));
+                assert!(!centroids[..i].contains(centroid));
+            }
+
+            let mut rng = StdRng::seed_from_u64(seed);
+            let result = _kmeans(4, &points, parallel, StoppingCriteria::default(), &Euclidean, &mut rng);
+            parallel_inertia += total_inertia(&result.clusters);
+
+            let mut rng = StdRng::seed_from_u64(seed);
+            let result = _kmeans(4, &points, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &Euclidean, &mut rng);
+            plus_plus_inertia += total_inertia(&result.clusters);
+        }
+
+        // On average, starting from k-means|| should end up about as good as starting from k-means++
+        assert!(parallel_inertia <= 1.1 * plus_plus_inertia, "{} vs {}", parallel_inertia, plus_plus_inertia);
+
+        // So few candidates that they have to be topped up
+        let sparse = InitMethod::KMeansParallel { l: 0.1, rounds: 1 };
+        let clusters = _initialize_clusters(4, &points, sparse, &Euclidean, &mut StdRng::seed_from_u64(0));
+        assert_eq!(clusters.len(), 4);
+    }
+
+    #[test]
+    fn greedy_kmeans_plus_plus_initialization() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use super::{_initialize_clusters, _kmeans, StoppingCriteria};
+
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ];
+        let greedy = InitMethod::GreedyKMeansPlusPlus;
+
+        let (mut greedy_inertia, mut plus_plus_inertia) = (0.0, 0.0);
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let centroids = Cluster::centroids(&_initialize_clusters(4, &points, greedy, &Euclidean, &mut rng));
+            assert_eq!(centroids.len(), 4);
+            for (i, centroid) in centroids.iter().enumerate() {
+                assert!(points.contains(centroid));
+                assert!(!centroids[..i].contains(centroid));
+            }
+
+            let mut rng = StdRng::seed_from_u64(seed);
+            let result = _kmeans(4, &points, greedy, StoppingCriteria::default(), &Euclidean, &mut rng);
+            greedy_inertia += total_inertia(&result.clusters);
+
+            let mut rng = StdRng::seed_from_u64(seed);
+            let result = _kmeans(4, &points, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &Euclidean, &mut rng);
+            plus_plus_inertia += total_inertia(&result.clusters);
+        }
+
+        // On average, the greedy picks should end up no worse than plain k-means++
+        assert!(greedy_inertia <= plus_plus_inertia, "{} vs {}", greedy_inertia, plus_plus_inertia);
+
+        // Every point in the same place leaves nothing to choose between
+        let clusters = _initialize_clusters(3, &[(1.0, 1.0); 5], greedy, &Euclidean, &mut StdRng::seed_from_u64(0));
+        assert_eq!(clusters.len(), 3);
+
+        // With more clusters than points, every point gets one
+        let clusters = _initialize_clusters(6, &points[..4], greedy, &Euclidean, &mut StdRng::seed_from_u64(0));
+        assert_eq!(clusters.len(), 4);
+        let result = _kmeans(6, &points[..4], greedy, StoppingCriteria::default(), &Euclidean, &mut StdRng::seed_from_u64(0));
+        assert!(result.converged);
+        assert!(result.clusters.iter().all(|c| c.size() == 1));
+    }
+
+    #[test]
+    fn weighted_selection() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use super::select_point;
+
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            assert_eq!(select_point(&[0.0, 0.0, 0.0, 1.0], &mut rng), 3);
+            assert_eq!(select_point(&[2.0, 0.0, 0.0, 0.0], &mut rng), 0);
+            assert_eq!(select_point(&[0.0, 1.0, 0.0, 3.0, 0.0], &mut rng) % 2, 1);
+            assert_eq!(select_point(&[5.0], &mut rng), 0);
+        }
+
+        let mut counts = [0; 3];
+        for _ in 0..3000 {
+            counts[select_point(&[1.0, 1.0, 1.0], &mut rng)] += 1;
+        }
+        assert!(counts.iter().all(|c| *c > 800), "{:?}", counts);
+    }
+
+    #[test]
+    fn nan_distances() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use super::{_initialize_clusters, shortest_center_distance};
+
+        let nan = (f64::NAN, 0.0);
+        assert_eq!(shortest_center_distance(&[&nan, &(3.0, 4.0)], &(0.0, 0.0), &Euclidean), 25.0);
+        assert_eq!(shortest_center_distance(&[&(3.0, 4.0), &nan], &(0.0, 0.0), &Euclidean), 25.0);
+        assert_eq!(shortest_center_distance(&[&nan], &(0.0, 0.0), &Euclidean), 0.0);
+        assert_eq!(shortest_center_distance(&[&(0.0, 0.0)], &nan, &Euclidean), 0.0);
+
+        // The point with a NaN coordinate can only be picked first, when every point is equally likely
+        let points = vec![(1f64, 2f64), (1.0, 3.0), nan, (8.0, 4.0), (8.0, 5.0), (9.0, 6.0)];
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let clusters = _initialize_clusters(3, &points, InitMethod::KMeansPlusPlus, &Euclidean, &mut rng);
+            assert_eq!(clusters.len(), 3);
+            assert!(clusters[1..].iter().all(|c| !c.centroid.0.is_nan()));
+        }
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn max_iter_caps_rounds() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0)
+        ];
+
+        // With no rounds allowed, the centroids are still the initial picks from the input
+        let clusters = kmeans_with_max_iter(2, points.clone(), 0);
+        for centroid in Cluster::centroids(&clusters) {
+            assert!(points.contains(&centroid));
+        }
+
+        let clusters = kmeans_with_max_iter(2, points.clone(), 100);
+        assert_eq!(clusters.iter().map(|c| c.points.len()).sum::<usize>(), points.len());
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn detailed_result() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0)
+        ];
+
+        let result = kmeans_detailed(2, points.clone(), 100, false);
+        assert!(result.converged);
+        assert!(result.iterations >= 1 && result.iterations <= 100);
+
+        let result = kmeans_detailed(2, points, 0, false);
+        assert!(!result.converged);
+        assert_eq!(result.iterations, 0);
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn deadline() {
+        use std::time::{Duration, Instant};
+
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0)
+        ];
+
+        let result = kmeans_with_deadline(2, points.clone(), Instant::now() + Duration::from_secs(60));
+        assert!(result.converged);
+
+        // Already passed, so the clustering is left as the starting centroids made it
+        let result = kmeans_with_deadline(2, points.clone(), Instant::now());
+        assert!(!result.converged);
+        assert_eq!(result.iterations, 0);
+        assert_eq!(result.clusters.len(), 2);
+        assert_eq!(result.labels.len(), points.len());
+        assert_eq!(result.clusters.iter().map(Cluster::size).sum::<usize>(), points.len());
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn progress_callback() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ];
+
+        for _ in 0..10 {
+            let mut rounds = vec![];
+            let clusters = kmeans_with_callback(4, points.clone(), |iteration, inertia| rounds.push((iteration, inertia)));
+
+            assert!(!rounds.is_empty());
+            for (i, (iteration, _)) in rounds.iter().enumerate() {
+                assert_eq!(*iteration, i + 1);
+            }
+            for pair in rounds.windows(2) {
+                assert!(pair[1].1 <= pair[0].1);
+            }
+            assert!((rounds.last().unwrap().1 - total_inertia(&clusters)).abs() < 1e-9);
+        }
+
+        assert!(kmeans_with_callback(2, Vec::<(f64, f64)>::new(), |_, _| panic!("no rounds to run")).is_empty());
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn inertia_history() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ];
+
+        for _ in 0..10 {
+            let result = kmeans_detailed(3, points.clone(), 100, true);
+            assert_eq!(result.inertia_history.len(), result.iterations);
+            for pair in result.inertia_history.windows(2) {
+                assert!(pair[1] <= pair[0]);
+            }
+            assert!((result.inertia_history.last().unwrap() - total_inertia(&result.clusters)).abs() < 1e-9);
+        }
+
+        assert_eq!(kmeans_detailed(3, points.clone(), 1, true).inertia_history.len(), 1);
+        assert!(kmeans_detailed(3, points.clone(), 0, true).inertia_history.is_empty());
+        assert!(kmeans_detailed(3, points, 100, false).inertia_history.is_empty());
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn unsigned_centroids() {
+        let points = vec![(0u32, 10u32), (2, 20), (4, 30)];
+        let clusters = kmeans(1, points);
+        assert_eq!(Cluster::centroids(&clusters), vec![(2, 20)]);
+
+        let points = vec![(0u16, 10u16, 100u16), (2, 20, 200), (4, 30, 300)];
+        let clusters = kmeans(1, points);
+        assert_eq!(Cluster::centroids(&clusters), vec![(2, 20, 200)]);
+
+        let points = vec![(0u64, 10u64, 100u64, 1000u64), (2, 20, 200, 2000), (4, 30, 300, 3000)];
+        let clusters = kmeans(1, points);
+        assert_eq!(Cluster::centroids(&clusters), vec![(2, 20, 200, 2000)]);
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn signed_centroids() {
+        let points = vec![(-4i32, 10i32), (-2, 20), (0, 30)];
+        let clusters = kmeans(1, points);
+        assert_eq!(Cluster::centroids(&clusters), vec![(-2, 20)]);
+
+        let points = vec![(-4i16, 10i16, -100i16), (-2, 20, -200), (0, 30, -300)];
+        let clusters = kmeans(1, points);
+        assert_eq!(Cluster::centroids(&clusters), vec![(-2, 20, -200)]);
+
+        let points = vec![(-4i64, 10i64, -100i64, 1000i64), (-2, 20, -200, 2000), (0, 30, -300, 3000)];
+        let clusters = kmeans(1, points);
+        assert_eq!(Cluster::centroids(&clusters), vec![(-2, 20, -200, 2000)]);
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn too_few_points() {
+        let points = vec![(1f64, 2f64), (1.0, 3.0), (2.0, 2.0)];
+
+        assert_eq!(try_kmeans(5, points.clone()), Err(KMeansError::TooFewPoints { k: 5, n: 3 }));
+        assert_eq!(try_kmeans(3, points).map(|clusters| clusters.len()), Ok(3));
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn empty_input() {
+        let points: Vec<(f64, f64)> = vec![];
+
+        assert!(kmeans(2, points.clone()).is_empty());
+        assert_eq!(try_kmeans(2, points), Err(KMeansError::NoData));
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn zero_clusters() {
+        let points = vec![(1f64, 2f64), (1.0, 3.0), (2.0, 2.0)];
+
+        assert!(kmeans(0, points.clone()).is_empty());
+        assert_eq!(try_kmeans(0, points), Ok(vec![]));
+    }
+
+    #[test]
+    fn squared_distances() {
+        assert_eq!((0f64, 0f64).dist_sq(&(3.0, 4.0)), 25.0);
+        assert_eq!((1u8, 1u8, 1u8).dist_sq(&(3, 4, 1)), 13.0);
+        assert_eq!((-1i32, 0i32, 0i32, 2i32).dist_sq(&(1, 0, 0, 0)), 8.0);
+    }
+
+    #[test]
+    fn mean_of_references() {
+        fn assert_same_mean<P: DataPoint + core::fmt::Debug>(points: &[P]) {
+            // Every other point, as if they were scattered about
+            let owned: Vec<P> = points.iter().step_by(2).cloned().collect();
+            let borrowed: Vec<&P> = points.iter().step_by(2).collect();
+            assert_eq!(P::mean_refs(&borrowed), P::mean(&owned));
+            assert_eq!(P::mean_refs(&[]), None);
+        }
+
+        assert_same_mean(&[(1f64, 2f64), (7.0, 3.0), (2.5, 4.0), (0.0, 0.0), (0.1, 0.2)]);
+        assert_same_mean(&[(1u8, 2u8, 3u8), (4, 4, 4), (9, 0, 5)]);
+        assert_same_mean(&[[1i32, -2, 3, 7], [4, 4, 4, 4], [-9, 0, 5, 2]]);
+        assert_same_mean(&[0.1f64, 0.7, 0.2, 100.0, 0.3]);
+        assert_same_mean(&[Rgb(255, 0, 10), Rgb(0, 0, 0), Rgb(128, 64, 9)]);
+        assert_same_mean(&[vec![0.1, 0.2, 0.3], vec![1.0, 1.0, 1.0], vec![0.7, -0.2, 5.0]]);
+        // Not overridden, so the references are cloned
+        assert_same_mean(&[0b1010u64, 0b0110, 0b0011, 0b1111, 0b0010]);
+    }
+
+    #[test]
+    fn weighted_mean_of_references() {
+        fn assert_same_mean<P: WeightedMean + core::fmt::Debug>(points: &[P]) {
+            let owned: Vec<P> = points.iter().step_by(2).cloned().collect();
+            let borrowed: Vec<&P> = points.iter().step_by(2).collect();
+            let weights: Vec<f64> = (1..=owned.len()).map(|w| w as f64).collect();
+            assert_eq!(P::weighted_mean_refs(&borrowed, &weights), P::weighted_mean(&owned, &weights));
+        }
+
+        assert_same_mean(&[(1f64, 2f64), (7.0, 3.0), (2.5, 4.0), (0.0, 0.0), (0.1, 0.2)]);
+        assert_same_mean(&[(1u8, 2u8, 3u8), (4, 4, 4), (9, 0, 5)]);
+        assert_same_mean(&[[1i32, -2, 3, 7], [4, 4, 4, 4], [-9, 0, 5, 2]]);
+        assert_same_mean(&[0.1f64, 0.7, 0.2, 100.0, 0.3]);
+        assert_same_mean(&[Rgb(255, 0, 10), Rgb(0, 0, 0), Rgb(128, 64, 9)]);
+        assert_same_mean(&[vec![0.1, 0.2, 0.3], vec![1.0, 1.0, 1.0], vec![0.7, -0.2, 5.0]]);
+    }
+
+    #[test]
+    fn predicting_new_points() {
+        let clusters = vec![Cluster::new(&(0f64, 0f64)), Cluster::new(&(10.0, 10.0))];
+
+        assert_eq!(predict(&clusters, &(1.0, 2.0)), Some(0));
+        assert_eq!(predict(&clusters, &(8.0, 9.0)), Some(1));
+        // Equidistant points go to the smaller centroid
+        assert_eq!(predict(&clusters, &(5.0, 5.0)), Some(0));
+        assert_eq!(predict_batch(&clusters, &[(1.0, 2.0), (8.0, 9.0), (11.0, 7.0)]), Some(vec![0, 1, 1]));
+
+        let points = [(3.0, 4.0), (8.0, 9.0), (5.0, 5.0), (40.0, 50.0)];
+        for point in points.iter() {
+            let (label, distance) = predict_with_distance(&clusters, point).unwrap();
+            assert_eq!(Some(label), predict(&clusters, point));
+            assert_eq!(distance, point.dist(&clusters[label].centroid));
+        }
+        assert_eq!(predict_with_distance(&clusters, &(3.0, 4.0)), Some((0, 5.0)));
+        assert_eq!(
+            predict_batch_with_distance(&clusters, &points),
+            points.iter().map(|p| predict_with_distance(&clusters, p)).collect::<Option<Vec<_>>>()
+        );
+
+        // There's no cluster to predict with no clusters at all
+        let none: Vec<Cluster<(f64, f64)>> = vec![];
+        assert_eq!(predict(&none, &(1.0, 2.0)), None);
+        assert_eq!(predict_with_distance(&none, &(1.0, 2.0)), None);
+        assert_eq!(predict_batch(&none, &points), None);
+        assert_eq!(predict_batch(&none, &[]), None);
+        assert_eq!(predict_batch_with_distance(&none, &points), None);
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn assigning_points() {
+        let clusters = vec![Cluster::new(&(f64::NAN, 0.0)), Cluster::new(&(0f64, 0f64)), Cluster::new(&(10.0, 10.0))];
+
+        assert_eq!(assign(&(1.0, 2.0), &clusters), Some(1));
+        assert_eq!(assign(&(8.0, 9.0), &clusters), Some(2));
+        // Equidistant points go to the smaller of the centroids they're nearest, and NaN is never
+        // nearest
+        assert_eq!(assign(&(5.0, 5.0), &clusters), Some(1));
+        assert_eq!(assign(&(5.0, 5.0), &[]), None);
+
+        let points = vec![(1f64, 2f64), (9.0, 5.0), (1.0, 3.0), (2.0, 2.0), (10.0, 6.0), (2.0, 3.0)];
+        let (clusters, labels) = kmeans_labels(2, points.clone());
+        assert_eq!(points.iter().map(|p| assign(p, &clusters)).collect::<Option<Vec<usize>>>(), Some(labels));
+    }
+
+    #[test]
+    fn distances_to_centroids() {
+        let clusters = vec![Cluster::new(&(0f64, 0f64)), Cluster::new(&(10.0, 10.0)), Cluster::new(&(3.0, 0.0))];
+        let distances = transform(&clusters, &[(3.0, 4.0), (10.0, 10.0)]);
+
+        assert_eq!(distances.len(), 2);
+        assert!(distances.iter().all(|row| row.len() == 3));
+        assert_eq!(distances[0], vec![5.0, f64::sqrt(85.0), 4.0]);
+        assert_eq!(distances[1], vec![f64::sqrt(200.0), 0.0, f64::sqrt(149.0)]);
+        assert_eq!(transform(&clusters, &[]), Vec::<Vec<f64>>::new());
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn labels_follow_input_order() {
+        let points = vec![
+            (1f64, 2f64), (9.0, 5.0), (1.0, 3.0), (2.0, 2.0), (10.0, 6.0), (2.0, 3.0), (8.0, 8.0)
+        ];
+
+        let (clusters, labels) = kmeans_labels(2, points.clone());
+
+        assert_eq!(labels.len(), points.len());
+        for (point, label) in points.iter().zip(labels.iter()) {
+            assert!(clusters[*label].points.contains(point));
+        }
+        assert_eq!(labels[0], labels[2]);
+        assert_eq!(labels[1], labels[4]);
+        assert_ne!(labels[0], labels[1]);
+    }
+
+    #[test]
+    fn borrowed_centroids() {
+        let clusters = vec![
+            Cluster { centroid: vec![0.0, 1.0], points: vec![vec![0.0, 1.0]] },
+            Cluster { centroid: vec![2.0, 3.0], points: vec![vec![2.0, 3.0]] }
+        ];
+
+        let borrowed = Cluster::centroids_ref(&clusters);
+        assert!(core::ptr::eq(borrowed[0], &clusters[0].centroid));
+        assert!(core::ptr::eq(borrowed[1], &clusters[1].centroid));
+        assert_eq!(borrowed.into_iter().cloned().collect::<Vec<_>>(), Cluster::centroids(&clusters));
+
+        let owned: Vec<Vec<f64>> = clusters.into_iter().map(Cluster::into_centroids).collect();
+        assert_eq!(owned, vec![vec![0.0, 1.0], vec![2.0, 3.0]]);
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn inertia() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ];
+
+        let clusters = kmeans(2, points);
+        let small = clusters.iter().find(|c| c.points.len() == 15).unwrap();
+
+        assert!((small.inertia() - 548.0 / 15.0).abs() < 1e-9);
+        assert!((total_inertia(&clusters) - 7913.0 / 90.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn silhouette() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (9.0, 5.0), (9.0, 6.0)
+        ];
+        let split = |at: usize| vec![
+            Cluster { centroid: (0.0, 0.0), points: points[..at].to_vec() },
+            Cluster { centroid: (0.0, 0.0), points: points[at..].to_vec() }
+        ];
+
+        // The two blobs are far apart compared to how spread out they are
+        let good = silhouette_score(&split(5));
+        assert!(good > 0.7, "{}", good);
+        assert!(silhouette_score(&split(3)) < good);
+        assert!(silhouette_score(&split(8)) < good);
+
+        // A point alone in its cluster counts as zero
+        let clusters = vec![
+            Cluster { centroid: (0.0, 0.0), points: vec![(0.0, 0.0)] },
+            Cluster { centroid: (10.5, 0.0), points: vec![(10.0, 0.0), (11.0, 0.0)] }
+        ];
+        assert!((silhouette_score(&clusters) - (0.9 + 10.0 / 11.0) / 3.0).abs() < 1e-12);
+
+        // There's nothing to compare against with only one cluster
+        assert_eq!(silhouette_score(&split(0)), 0.0);
+        assert_eq!(silhouette_score::<(f64, f64)>(&[]), 0.0);
+    }
+
+    #[test]
+    fn davies_bouldin() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (9.0, 5.0), (9.0, 6.0)
+        ];
+        let clusters = vec![
+            Cluster { centroid: (1.6, 2.8), points: points[..5].to_vec() },
+            Cluster { centroid: (8.4, 5.2), points: points[5..].to_vec() }
+        ];
+        assert!((davies_bouldin_index(&clusters) - 0.23513782269289146).abs() < 1e-12);
+
+        // Splitting one blob in two makes those halves easy to confuse
+        let mut three = clusters.clone();
+        three[0] = Cluster { centroid: (1.0, 2.5), points: points[..2].to_vec() };
+        three.push(Cluster { centroid: (2.0, 3.0), points: points[2..5].to_vec() });
+        assert!(davies_bouldin_index(&three) > davies_bouldin_index(&clusters));
+
+        // Empty clusters are ignored, but ones in the same place can't be told apart
+        let mut with_empty = clusters.clone();
+        with_empty.push(Cluster::new(&(100.0, 100.0)));
+        assert_eq!(davies_bouldin_index(&with_empty), davies_bouldin_index(&clusters));
+        let mut doubled = clusters.clone();
+        doubled.push(clusters[0].clone());
+        assert_eq!(davies_bouldin_index(&doubled), f64::INFINITY);
+
+        assert_eq!(davies_bouldin_index(&clusters[..1]), 0.0);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn information_criteria() {
+        let file = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/test_data.txt")).unwrap();
+        let data: Vec<Vec<f64>> = file.trim().lines()
+            .map(|line| line.split_whitespace().map(|s| s.parse().unwrap()).collect())
+            .collect();
+
+        for seed in 0..10 {
+            let one = kmeans_seeded(1, data.clone(), seed);
+            let two = kmeans_seeded(2, data.clone(), seed);
+            assert!(bic(&two) > bic(&one));
+            assert!(aic(&two) > aic(&one));
+
+            // Two clusters in three dimensions have a weight, six coordinates and a variance
+            let n = data.len() as f64;
+            assert!((aic(&two) - bic(&two) - 8.0 * (n.ln() / 2.0 - 1.0)).abs() < 1e-9);
+        }
+
+        // Too few points to estimate a variance from
+        let points = [vec![1.0, 2.0], vec![3.0, 4.0]];
+        let clusters = vec![Cluster { centroid: points[0].clone(), points: points[..1].to_vec() }, Cluster { centroid: points[1].clone(), points: points[1..].to_vec() }];
+        assert_eq!(aic(&clusters), f64::NEG_INFINITY);
+        assert_eq!(bic(&clusters), f64::NEG_INFINITY);
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn automatic_k() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use super::_auto;
+
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (9.0, 5.0), (9.0, 6.0)
+        ];
+
+        for seed in 0..10 {
+            let (k, clusters) = _auto(1..=5, points.clone(), silhouette_score, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(k, 2);
+            assert_eq!(clusters.len(), 2);
+            assert!(clusters.iter().all(|c| c.points.len() == 5));
+        }
+
+        // More clusters always means less inertia
+        let (k, clusters) = auto_kmeans_with_score(1..=4, points.clone(), |c| -total_inertia(c));
+        assert_eq!((k, clusters.len()), (4, 4));
+
+        assert_eq!(auto_kmeans(core::ops::RangeInclusive::new(3, 2), points.clone()), (0, vec![]));
+
+        // Only up to as many clusters as there are points are tried
+        let (k, clusters) = auto_kmeans_with_score(1..=5, points[..3].to_vec(), |c| -total_inertia(c));
+        assert_eq!((k, clusters.len()), (3, 3));
+        assert_eq!(auto_kmeans(1..=5, points[..3].to_vec()).1.iter().map(Cluster::size).sum::<usize>(), 3);
+        assert_eq!(auto_kmeans(4..=5, points[..3].to_vec()), (0, vec![]));
+    }
+
+    #[test]
+    fn outliers() {
+        let cluster = Cluster {
+            centroid: (0f64, 0f64),
+            points: vec![(1.0, 0.0), (0.0, -2.0), (30.0, 40.0), (-1.0, 0.0), (0.0, 1.0)]
+        };
+
+        assert_eq!(cluster.point_distances(), vec![1.0, 2.0, 50.0, 1.0, 1.0]);
+        assert_eq!(cluster.outliers(10.0), vec![&(30.0, 40.0)]);
+        assert_eq!(cluster.outliers(1.0), vec![&(0.0, -2.0), &(30.0, 40.0)]);
+        assert!(cluster.outliers(50.0).is_empty());
+
+        assert_eq!(cluster.outliers_by_percentile(80.0), vec![&(30.0, 40.0)]);
+        assert_eq!(cluster.outliers_by_percentile(60.0), vec![&(0.0, -2.0), &(30.0, 40.0)]);
+        assert!(cluster.outliers_by_percentile(100.0).is_empty());
+        assert_eq!(cluster.outliers_by_percentile(0.0).len(), 2);
+        assert!(Cluster::new(&(0f64, 0f64)).outliers_by_percentile(50.0).is_empty());
+    }
+
+    #[test]
+    fn nearest_and_farthest_points() {
+        let cluster = Cluster {
+            centroid: (0f64, 0f64),
+            points: vec![(3.0, 4.0), (1.0, 0.0), (0.0, -2.0), (30.0, 40.0), (0.0, 1.0), (-6.0, 8.0)]
+        };
+
+        assert_eq!(cluster.nearest_points(3), vec![&(1.0, 0.0), &(0.0, 1.0), &(0.0, -2.0)]);
+        assert_eq!(cluster.farthest_points(3), vec![&(30.0, 40.0), &(-6.0, 8.0), &(3.0, 4.0)]);
+        assert_eq!(cluster.farthest_points(5), vec![&(30.0, 40.0), &(-6.0, 8.0), &(3.0, 4.0), &(0.0, -2.0), &(1.0, 0.0)]);
+
+        assert_eq!(cluster.nearest_points(10).len(), 6);
+        assert_eq!(cluster.farthest_points(10).len(), 6);
+        assert!(cluster.nearest_points(0).is_empty());
+        assert!(Cluster::new(&(0f64, 0f64)).farthest_points(2).is_empty());
+    }
+
+    #[test]
+    fn cluster_membership() {
+        let points = vec![(1f64, 1f64), (1.5, 2.0), (9.0, 1.0), (9.5, 1.5)];
+        for seed in 0..10 {
+            let clusters = kmeans_seeded(2, points.clone(), seed);
+            let left = clusters.iter().find(|c| c.contains(&(1.0, 1.0))).unwrap();
+            assert!(left.contains(&(1.5, 2.0)));
+            assert!(!left.contains(&(9.0, 1.0)));
+            // Near a point isn't in the cluster
+            assert!(!left.contains(&(1.0, 1.0000001)));
+        }
+        assert!(!Cluster::new(&0.0).contains(&0.0));
+    }
+
+    #[test]
+    fn display_clusters() {
+        let clusters = vec![
+            Cluster { centroid: (3.0, 3.0), points: vec![(2.0, 3.0), (4.0, 3.0)] },
+            Cluster { centroid: (8.5, 6.0), points: vec![(8.5, 6.0)] },
+            Cluster::new(&(0.0, 0.0))
+        ];
+
+        assert_eq!(clusters[0].to_string(), "Cluster(centroid=(3.0, 3.0), n=2)");
+        assert_eq!(format!("{}", Cluster { centroid: [1u8, 2], points: vec![[1, 2]] }), "Cluster(centroid=[1, 2], n=1)");
+        assert_eq!(
+            summary(&clusters),
+            "0: Cluster(centroid=(3.0, 3.0), n=2)\n1: Cluster(centroid=(8.5, 6.0), n=1)\n2: Cluster(centroid=(0.0, 0.0), n=0)\n"
+        );
+        assert_eq!(summary::<f64>(&[]), "");
+    }
+
+    #[test]
+    fn cluster_points() {
+        let cluster = Cluster { centroid: (1u8, 1u8), points: vec![(0, 0), (2, 2), (1, 1)] };
+        assert_eq!(cluster.size(), 3);
+        assert!(!cluster.is_empty());
+        assert!(Cluster::new(&(1u8, 1u8)).is_empty());
+
+        let mut borrowed = vec![];
+        for p in &cluster {
+            borrowed.push(*p);
+        }
+        assert_eq!(borrowed, cluster.points);
+        assert_eq!(cluster.into_iter().collect::<Vec<_>>(), vec![(0, 0), (2, 2), (1, 1)]);
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let close = |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).abs() < 1e-12 && (a.1 - b.1).abs() < 1e-12;
+        let points = vec![(1f64, 2f64), (9.0, 5.0), (1.0, 3.0), (2.0, 2.0), (10.0, 6.0), (2.0, 3.0), (8.0, 8.0)];
+
+        let mut cluster = Cluster::new(&(100f64, 100f64));
+        for p in points.iter() {
+            cluster.add_point(*p);
+            let mut recalculated = cluster.clone();
+            recalculated.recalculate_centroid();
+            assert!(close(cluster.centroid, recalculated.centroid));
+        }
+        assert_eq!(cluster.points, points);
+
+        for p in [(9.0, 5.0), (1.0, 2.0), (8.0, 8.0)] {
+            assert!(cluster.remove_point(&p));
+            let mut recalculated = cluster.clone();
+            recalculated.recalculate_centroid();
+            assert!(close(cluster.centroid, recalculated.centroid));
+        }
+        assert_eq!(cluster.points, vec![(1.0, 3.0), (2.0, 2.0), (10.0, 6.0), (2.0, 3.0)]);
+        assert!(!cluster.remove_point(&(9.0, 5.0)));
+
+        // The last point out leaves the centroid behind
+        let mut cluster = Cluster::new(&(0f64, 0f64));
+        cluster.add_point((3.0, 4.0));
+        assert_eq!(cluster.centroid, (3.0, 4.0));
+        assert!(cluster.remove_point(&(3.0, 4.0)));
+        assert_eq!(cluster.centroid, (3.0, 4.0));
+        assert!(cluster.is_empty());
+    }
+
+    #[test]
+    fn sorted_by_size() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use super::{_kmeans, StoppingCriteria};
 
-pub mod prelude {
-    pub use super::{
-        kmeans,
-        types::{Cluster, DataPoint}
-    };
-}
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0), (3.0, 3.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (9.0, 5.0), (9.0, 6.0),
+            (20.0, 20.0), (21.0, 20.0), (20.0, 21.0), (21.0, 21.0)
+        ];
 
-/// Clustering algorithm using k-means++
-pub fn kmeans<P: DataPoint>(k: usize, datapoints: Vec<P>) -> Vec<Cluster<P>> {
-    // Initialize cluster means
-    let mut clusters = _initialize_clusters(k, datapoints.clone());
+        for seed in 0..10 {
+            let mut first = kmeans_seeded(3, points.clone(), seed);
+            let mut second = kmeans_seeded(3, points.clone(), seed);
+            Cluster::sort_by_size(&mut first);
+            Cluster::sort_by_size(&mut second);
+            assert_eq!(format!("{:?}", first), format!("{:?}", second));
+            assert!(first.windows(2).all(|w| w[0].size() >= w[1].size()));
 
-    // Make the initial clusterings
-    for point in datapoints.iter() {
-        _cluster(point, &mut clusters);
+            // The labels still point at the right clusters after sorting
+            let mut result = _kmeans(3, &points, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &Euclidean, &mut StdRng::seed_from_u64(seed));
+            result.sort_by_size();
+            assert_eq!(result.clusters, first);
+            for (point, label) in points.iter().zip(result.labels.iter()) {
+                assert!(result.clusters[*label].points.contains(point));
+            }
+        }
+
+        // Equal sizes fall back to the centroids
+        let mut clusters = vec![
+            Cluster { centroid: (5, 0), points: vec![(5, 0)] },
+            Cluster { centroid: (1, 9), points: vec![(1, 9)] },
+            Cluster { centroid: (1, 2), points: vec![(1, 2), (1, 2)] }
+        ];
+        Cluster::sort_by_size(&mut clusters);
+        assert_eq!(Cluster::centroids(&clusters), vec![(1, 2), (1, 9), (5, 0)]);
     }
 
-    // Store the current clustering for comparison later
-    let mut prev_clusters = clusters.clone();
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn fit_predict_labels() {
+        let points = vec![
+            (1f64, 2f64), (9.0, 5.0), (1.0, 3.0), (2.0, 2.0), (10.0, 6.0), (2.0, 3.0), (8.0, 8.0)
+        ];
+
+        for k in 1..=3 {
+            let labels = fit_predict(k, points.clone());
+            assert_eq!(labels.len(), points.len());
+            assert!(labels.iter().all(|label| *label < k));
+        }
 
-    // Recalculate the means based on the points in the cluster
-    for cluster in clusters.iter_mut() {
-        cluster.recalculate_centroid();
-        cluster.points.clear();
+        let labels = fit_predict(2, points.clone());
+        assert_eq!(labels.iter().map(|l| *l == labels[0]).collect::<Vec<bool>>(), vec![true, false, true, true, false, true, false]);
+        assert_eq!(fit_predict(0, points), Vec::<usize>::new());
     }
-    // Cluster again
-    for point in datapoints.iter() {
-        _cluster(point, &mut clusters);
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn centroids_only() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0), (2.0, 2.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (9.0, 5.0)
+        ];
+        let mut clusters = kmeans_centroids_only(2, points.clone());
+        clusters.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(clusters, vec![((10.0 / 6.0, 16.0 / 6.0), 6), ((8.25, 5.0), 4)]);
+
+        assert_eq!(kmeans_centroids_only(1, points.clone()).len(), 1);
+        assert_eq!(kmeans_centroids_only(0, points), vec![]);
+        assert_eq!(kmeans_centroids_only(2, Vec::<(f64, f64)>::new()), vec![]);
     }
 
-    // Rinse, repeat; until the clusters cease to change
-    while prev_clusters != clusters {
-        prev_clusters = clusters.clone();
-        for cluster in clusters.iter_mut() {
-            cluster.recalculate_centroid();
-            cluster.points.clear();
-        }
-        for point in datapoints.iter() {
-            _cluster(point, &mut clusters);
+    #[test]
+    fn deterministic_ties() {
+        use super::{_lloyd, _nearest, StoppingCriteria};
+
+        // (1, 0) is exactly between the two, and goes to the smaller whichever order they're in
+        assert_eq!(_nearest(&[(0f64, 0f64), (2.0, 0.0)], &(1.0, 0.0), &Euclidean), 0);
+        assert_eq!(_nearest(&[(2f64, 0f64), (0.0, 0.0)], &(1.0, 0.0), &Euclidean), 1);
+        assert_eq!(_nearest(&[[0u8, 2], [0, 0]], &[0, 1], &Euclidean), 1);
+        assert_eq!(_nearest(&[vec![1.0, 0.0], vec![0.0, 1.0]], &vec![0.0, 0.0], &Euclidean), 1);
+        assert_eq!(_nearest(&[Rgb(0, 0, 2), Rgb(0, 0, 0)], &Rgb(0, 0, 1), &Euclidean), 1);
+
+        let points = vec![(0f64, 0f64), (1.0, 0.0), (2.0, 0.0)];
+        for start in [vec![(0.0, 0.0), (2.0, 0.0)], vec![(2.0, 0.0), (0.0, 0.0)]] {
+            let mut clusters = _lloyd(start, &points, StoppingCriteria::default(), &Euclidean).clusters;
+            clusters.sort_by(|a, b| a.centroid.partial_cmp(&b.centroid).unwrap());
+            assert_eq!(clusters[0].points, vec![(0.0, 0.0), (1.0, 0.0)]);
+            assert_eq!(clusters[1].points, vec![(2.0, 0.0)]);
         }
+
+        let clusters = vec![Cluster::new(&(4f64, 4f64)), Cluster::new(&(4.0, 0.0))];
+        assert_eq!(assign(&(4.0, 2.0), &clusters), Some(1));
     }
 
-    // Return the final clustering
-    clusters
-}
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn manhattan_metric() {
+        use super::_nearest;
 
-/// Initializes the clusters using an initialization algorithm based on [k-means++].
-///
-/// [k-means++]: https://en.wikipedia.org/wiki/K-means%2B%2B#Improved_initialization_algorithm
-fn _initialize_clusters<P: DataPoint>(k: usize, mut datapoints: Vec<P>) -> Vec<Cluster<P>> {
-    /// Returns one of the `centers` is closest to `point`.
-    fn shortest_center_distance<P: DataPoint>(centers: &[P], point: &P) -> f64 {
-        centers.iter()
-            // calculate the distances between each center and `point`
-            .map(|c| c.dist(point))
-            // take the minimum of those distances
-            .fold(f64::INFINITY, f64::min)
+        assert_eq!(Manhattan.distance(&(0f64, 0f64), &(3.0, 4.0)), 7.0);
+        assert_eq!(Euclidean.distance(&(0f64, 0f64), &(3.0, 4.0)), 5.0);
+
+        // (5, 0) is nearer (3, 4) as the crow flies, but nearer the origin by taxicab
+        let centroids = [(0f64, 0f64), (3.0, 4.0)];
+        assert_eq!(_nearest(&centroids, &(5.0, 0.0), &Euclidean), 1);
+        assert_eq!(_nearest(&centroids, &(5.0, 0.0), &Manhattan), 0);
+
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (9.0, 5.0), (9.0, 6.0)
+        ];
+        let clusters = kmeans_with_metric(2, points, Manhattan);
+        let mut centroids = Cluster::centroids(&clusters);
+        centroids.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(centroids, vec![(1.6, 2.8), (8.4, 5.2)]);
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn chebyshev_metric() {
+        use super::_nearest;
+
+        assert_eq!(Chebyshev.distance(&(0f64, 0f64), &(3.0, -4.0)), 4.0);
+        assert_eq!(Chebyshev.distance(&[1u8, 5, 2], &[4, 5, 0]), 3.0);
+
+        // Going diagonally to the corner at (4, 4) costs no more than going straight along one axis
+        let centroids = [(0f64, 0f64), (9.0, 4.0)];
+        assert_eq!(_nearest(&centroids, &(4.0, 4.0), &Euclidean), 1);
+        assert_eq!(_nearest(&centroids, &(4.0, 4.0), &Chebyshev), 0);
+
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (9.0, 5.0), (9.0, 6.0)
+        ];
+        let clusters = kmeans_with_metric(2, points, Chebyshev);
+        let mut centroids = Cluster::centroids(&clusters);
+        centroids.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(centroids, vec![(1.6, 2.8), (8.4, 5.2)]);
     }
 
-    /// Selects a point using a weighted distribution based on `shortest_center_distance` squared.
-    fn select_point(distribution: &[f64]) -> usize {
-        // Generate the selection criterion for each point.
-        // We'll generate a random number and select the point whose selection criterion is less
-        // than that number, but whose following point's is greater than that number.
-        // Like throwing a dart at a number line and seeing what range of values it falls in.
-        let distr_sum = distribution.iter().fold(0.0, f64::add);
-        let mut selection_criteria = Vec::with_capacity(distribution.len());
-        for i in 0..distribution.len() {
-            let sum = distribution[0..i].iter().fold(0.0, f64::add);
-            selection_criteria.push(distribution[i] + sum);
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn minkowski_metric() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use super::{_kmeans, StoppingCriteria};
+
+        fn run<M: Metric<(f64, f64)>>(points: &[(f64, f64)], metric: M, seed: u64) -> KMeansResult<(f64, f64)> {
+            let mut rng = StdRng::seed_from_u64(seed);
+            _kmeans(3, points, InitMethod::default(), StoppingCriteria::default(), &metric, &mut rng)
         }
-        let rn: f64 = thread_rng().gen_range(0.0, distr_sum);
 
-        let mut selection = 0;
-        for i in 1..(selection_criteria.len() - 1) {
-            if rn > selection_criteria[i] {
-                selection = i;
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ];
+        for a in points.iter() {
+            for b in points.iter() {
+                assert_eq!(Minkowski { p: 1.0 }.distance(a, b), Manhattan.distance(a, b));
+                assert_eq!(Minkowski { p: 2.0 }.distance(a, b), Euclidean.distance(a, b));
+                assert_eq!(Minkowski { p: 2.0 }.distance_sq(a, b), Euclidean.distance_sq(a, b));
+                assert_eq!(Minkowski { p: f64::INFINITY }.distance(a, b), Chebyshev.distance(a, b));
             }
+        }
+        assert!((Minkowski { p: 3.0 }.distance(&(0f64, 0f64), &(3.0, 3.0)) - 54f64.cbrt()).abs() < 1e-12);
 
-            if rn <= selection_criteria[i] {
-                break;
-            }
+        for seed in 0..10 {
+            assert_eq!(run(&points, Minkowski { p: 1.0 }, seed), run(&points, Manhattan, seed));
+            assert_eq!(run(&points, Minkowski { p: 2.0 }, seed), run(&points, Euclidean, seed));
         }
 
-        selection
+        let clusters = kmeans_with_metric(2, points, Minkowski { p: 3.0 });
+        assert_eq!(clusters.len(), 2);
     }
 
-    let mut clusters = Vec::with_capacity(k);
-    // First centroid is selected with a uniform distribution
-    let first_point = datapoints.remove(thread_rng().gen_range(0, datapoints.len()));
-    let mut distribution: Vec<f64> = datapoints.iter()
-        .map(|p| first_point.dist(p).powi(2))
-        .collect();
+    #[test]
+    fn jaccard_metric() {
+        assert_eq!(Jaccard.distance(&vec![1u32, 2, 3], &vec![2, 3, 4]), 0.5);
+        assert_eq!(Jaccard.distance(&vec![1u32, 2], &vec![1, 2]), 0.0);
+        assert_eq!(Jaccard.distance(&vec![1u32, 2], &vec![3, 4, 5]), 1.0);
+        assert_eq!(Jaccard.distance(&vec![1u32], &vec![1, 2, 3, 4]), 0.75);
+        assert_eq!(Jaccard.distance(&Vec::<u32>::new(), &vec![]), 0.0);
+        assert_eq!(Jaccard.distance(&vec!["a", "b"], &vec!["b", "c"]), 1.0 - 1.0 / 3.0);
+    }
 
-    // Keep selecting unique points until we have `k` centroids
-    while clusters.len() < k {
-        let point = datapoints.remove(select_point(&distribution));
-        clusters.push(Cluster::new(&point));
-        let centroids = Cluster::centroids(&clusters);
-        distribution = datapoints.iter()
-            .map(|p| shortest_center_distance(&centroids, p).powi(2))
-            .collect()
+    #[test]
+    fn gower_metric() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use crate::{_kmeans, StoppingCriteria};
+        use Field::{Categorical, Numeric};
+        const RED: u64 = 0;
+        const BLUE: u64 = 1;
+
+        // Age, income, favorite color
+        let people = vec![
+            MixedPoint(vec![Numeric(20.0), Numeric(30_000.0), Categorical(RED)]),
+            MixedPoint(vec![Numeric(25.0), Numeric(35_000.0), Categorical(RED)]),
+            MixedPoint(vec![Numeric(22.0), Numeric(32_000.0), Categorical(BLUE)]),
+            MixedPoint(vec![Numeric(60.0), Numeric(90_000.0), Categorical(BLUE)]),
+            MixedPoint(vec![Numeric(55.0), Numeric(80_000.0), Categorical(BLUE)]),
+            MixedPoint(vec![Numeric(60.0), Numeric(85_000.0), Categorical(BLUE)])
+        ];
+
+        let gower = Gower::fit(&people);
+        assert_eq!(gower.ranges, vec![40.0, 60_000.0, 0.0]);
+        // Three quarters of the age range, three quarters of the income range, and a different color
+        assert!((gower.distance(&people[1], &people[4]) - (0.75 + 0.75 + 1.0) / 3.0).abs() < 1e-12);
+        assert_eq!(gower.distance(&people[3], &people[3]), 0.0);
+        assert_eq!(gower.distance(&people[0], &MixedPoint(vec![Categorical(RED), Numeric(30_000.0), Categorical(RED)])), 1.0 / 3.0);
+        // Unfitted, the numbers are taken to be scaled already
+        assert_eq!(MixedPoint(vec![Numeric(0.25), Categorical(RED)]).dist(&MixedPoint(vec![Numeric(0.75), Categorical(BLUE)])), 0.75);
+
+        assert_eq!(
+            MixedPoint::mean(&people[3..]),
+            Some(MixedPoint(vec![Numeric(175.0 / 3.0), Numeric(85_000.0), Categorical(BLUE)]))
+        );
+        // A tie between categories goes to the smaller id
+        assert_eq!(MixedPoint::mean(&people[1..3]).unwrap().0[2], Categorical(RED));
+        assert_eq!(MixedPoint::mean(&[]), None);
+
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let clusters = _kmeans(2, &people, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &gower, &mut rng).clusters;
+            let mut groups: Vec<Vec<MixedPoint>> = clusters.into_iter()
+                .map(|c| c.points)
+                .collect();
+            groups.sort_by(|a, b| a[0].tie_order(&b[0]));
+            assert_eq!(groups, vec![people[..3].to_vec(), people[3..].to_vec()]);
+        }
     }
 
-    clusters
-}
+    #[test]
+    fn dynamic_time_warping_metric() {
+        let dtw = DynamicTimeWarping;
+        assert_eq!(dtw.distance(&vec![1.0, 2.0, 3.0], &vec![1.0, 2.0, 3.0]), 0.0);
+        // Holding a value for longer doesn't cost anything
+        assert_eq!(dtw.distance(&vec![1.0, 2.0, 3.0], &vec![1.0, 1.0, 2.0, 2.0, 2.0, 3.0]), 0.0);
+        assert_eq!(dtw.distance(&vec![0.0, 1.0, 0.0], &vec![0.0, 0.0, 1.0, 0.0]), 0.0);
+        assert_eq!(dtw.distance(&vec![1.0, 2.0], &vec![1.0, 5.0]), 3.0);
+        // The 4 has to pair with something, and the 3 is nearest
+        assert_eq!(dtw.distance(&vec![1.0, 3.0], &vec![1.0, 4.0, 3.0]), 1.0);
+        assert_eq!(dtw.distance(&vec![], &vec![]), 0.0);
+        assert_eq!(dtw.distance(&vec![], &vec![1.0]), f64::INFINITY);
+    }
 
-/// Assigns a point to the cluster whose centroid is closest
-fn _cluster<P: DataPoint>(p: &P, clusters: &mut [Cluster<P>]) {
-    let mut closest_cluster = 0;
-    for c in 1..clusters.len() {
-        if P::dist(p, &clusters[c].centroid) < P::dist(p, &clusters[closest_cluster].centroid) {
-            closest_cluster = c;
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn haversine_metric() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use super::{_geographic, _kmeans, StoppingCriteria};
+
+        let (london, paris) = ((51.5074, -0.1278), (48.8566, 2.3522));
+        let (new_york, boston) = ((40.7128, -74.0060), (42.3601, -71.0589));
+        let (tokyo, osaka) = ((35.6762, 139.6503), (34.6937, 135.5023));
+
+        assert!((Haversine::default().distance(&london, &paris) - 343.5).abs() < 1.0);
+        assert!((Haversine::meters().distance(&new_york, &boston) - 306_000.0).abs() < 1000.0);
+        assert_eq!(Haversine::default().distance(&tokyo, &tokyo), 0.0);
+        // Halfway around the world
+        let antipode = Haversine { radius: 1.0 }.distance(&(0.0, 0.0), &(0.0, 180.0));
+        assert!((antipode - core::f64::consts::PI).abs() < 1e-12);
+
+        let cities = vec![london, new_york, tokyo, paris, boston, osaka];
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let plain = _kmeans(3, &cities, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &Haversine::default(), &mut rng).clusters;
+            let geographic = _geographic(3, cities.clone(), Haversine::default(), &mut rng);
+            for clusters in [plain, geographic] {
+                let mut groups: Vec<Vec<(f64, f64)>> = clusters.into_iter().map(|c| c.points).collect();
+                groups.sort_by(|a, b| a.partial_cmp(b).unwrap());
+                assert_eq!(groups, vec![vec![tokyo, osaka], vec![new_york, boston], vec![london, paris]]);
+            }
         }
+        assert_eq!(kmeans_geographic(2, cities, Haversine::default()).len(), 2);
+
+        // Averaging the degrees of points either side of the 180th meridian lands on the other side
+        // of the world
+        let fiji = vec![(-17.0, 179.0), (-18.0, -179.0)];
+        let clusters = kmeans_geographic(1, fiji.clone(), Haversine::default());
+        assert!(clusters[0].centroid.1.abs() > 179.9);
+        assert!(kmeans_with_metric(1, fiji, Haversine::default())[0].centroid.1.abs() < 0.1);
     }
-    clusters[closest_cluster].points.push(p.clone());
-}
 
-#[cfg(test)]
-mod test {
-    #[cfg(feature = "alloc")]
-    use alloc::{
-        string::String,
-        vec::Vec
-    };
+    #[test]
+    fn cosine_metric() {
+        use super::_nearest;
 
-    use crate::prelude::*;
+        assert_eq!(Cosine.distance(&(1f64, 0f64), &(0.0, 1.0)), 1.0);
+        assert_eq!(Cosine.distance(&(1f64, 0f64), &(-2.0, 0.0)), 2.0);
+        assert_eq!(Cosine.distance(&(3f64, 4f64), &(6.0, 8.0)), 0.0);
+        assert_eq!(Cosine.distance(&(0f64, 0f64), &(1.0, 0.0)), 2.0);
+
+        // The long centroid is far away from every unit vector, but some of them point nearly along it
+        let centroids = [(10f64, 0f64), (0.0, 1.0)];
+        let points = [(1f64, 0f64), (0.8, 0.6), (0.6, 0.8), (0.0, 1.0)];
+        let euclidean: Vec<usize> = points.iter().map(|p| _nearest(&centroids, p, &Euclidean)).collect();
+        let cosine: Vec<usize> = points.iter().map(|p| _nearest(&centroids, p, &Cosine)).collect();
+        assert_eq!(euclidean, vec![1, 1, 1, 1]);
+        assert_eq!(cosine, vec![0, 0, 1, 1]);
+    }
 
+    #[cfg(feature = "thread_rng")]
     #[test]
-    fn float_clustering() {
+    fn best_of_restarts() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use super::{_best_of, StoppingCriteria};
+
         let points = vec![
             (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
             (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
@@ -151,29 +2843,442 @@ mod test {
             (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
         ];
 
-        let clusters = kmeans(2, points);
-        let centroids = Cluster::centroids(&clusters);
+        for seed in 0..20 {
+            let once = _best_of(4, points.clone(), 1, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &mut StdRng::seed_from_u64(seed));
+            let best = _best_of(4, points.clone(), 10, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &mut StdRng::seed_from_u64(seed));
 
-        assert!(centroids.contains(&(71.0/9.0, 113.0/18.0)));
-        assert!(centroids.contains(&(46.0/15.0, 13.0/5.0)));
+            assert!(total_inertia(&best.clusters) <= total_inertia(&once.clusters));
+        }
+
+        assert_eq!(kmeans_best_of(2, points, 3).len(), 2);
     }
 
     #[test]
-    fn int_clustering() {
+    fn supplied_centroids() {
         let points = vec![
-            (1u8, 2u8), (1, 3), (2, 2), (2, 3), (2, 4),
-            (3, 1), (3, 2), (3, 3), (3, 4), (4, 1), (4, 2), (4, 3),
-            (4, 4), (5, 2), (5, 3),
-            (6, 5), (6, 6), (6, 7), (7, 5), (7, 6), (7, 7), (7, 8),
-            (8, 4), (8, 5), (8, 6), (8, 7), (8, 8), (9, 5), (9, 6),
-            (9, 7), (9, 8), (10, 6), (10, 7)
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (9.0, 5.0), (9.0, 6.0)
         ];
 
-        let clusters = kmeans(2, points);
-        let centroids = Cluster::centroids(&clusters);
+        let clusters = kmeans_from_centroids(vec![(0.0, 0.0), (10.0, 10.0)], points.clone()).unwrap();
+        assert_eq!(Cluster::centroids(&clusters), vec![(1.6, 2.8), (8.4, 5.2)]);
+
+        assert_eq!(kmeans_from_centroids(vec![], points.clone()), Err(KMeansError::NoCentroids));
+        assert_eq!(
+            kmeans_from_centroids(vec![(0.0, 0.0); 11], points),
+            Err(KMeansError::TooFewPoints { k: 11, n: 10 })
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn clones_points_once() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use super::{_lloyd, StoppingCriteria};
+        use crate::metric::Euclidean;
+
+        static CLONES: AtomicUsize = AtomicUsize::new(0);
 
-        assert!(centroids.contains(&(7, 7)) || centroids.contains(&(8, 8)));
-        assert!(centroids.contains(&(3, 3)));
+        #[derive(Debug, PartialEq)]
+        struct Counted(f64, f64);
+
+        impl Clone for Counted {
+            fn clone(&self) -> Self {
+                CLONES.fetch_add(1, Ordering::SeqCst);
+                Counted(self.0, self.1)
+            }
+        }
+
+        impl DataPoint for Counted {
+            fn dist(&self, other: &Self) -> f64 {
+                (self.0, self.1).dist(&(other.0, other.1))
+            }
+
+            fn mean(ps: &[Self]) -> Option<Self> {
+                let sum = ps.iter().fold((0.0, 0.0), |acc, p| (acc.0 + p.0, acc.1 + p.1));
+                Some(Counted(sum.0 / ps.len() as f64, sum.1 / ps.len() as f64))
+            }
+
+            fn mean_refs(ps: &[&Self]) -> Option<Self> {
+                let sum = ps.iter().fold((0.0, 0.0), |acc, p| (acc.0 + p.0, acc.1 + p.1));
+                Some(Counted(sum.0 / ps.len() as f64, sum.1 / ps.len() as f64))
+            }
+        }
+
+        let points = vec![
+            Counted(1.0, 2.0), Counted(1.0, 3.0), Counted(2.0, 2.0), Counted(2.0, 3.0), Counted(2.0, 4.0),
+            Counted(8.0, 4.0), Counted(8.0, 5.0), Counted(8.0, 6.0), Counted(9.0, 5.0), Counted(9.0, 6.0)
+        ];
+        let (n, k) = (points.len(), 2);
+
+        let result = _lloyd(vec![Counted(1.0, 2.0), Counted(1.0, 3.0)], &points, StoppingCriteria::default(), &Euclidean);
+
+        // Only the final clusters hold clones, however many rounds it took to get there
+        assert!(result.iterations >= 2);
+        assert!(CLONES.load(Ordering::SeqCst) <= n + k);
+    }
+
+    #[test]
+    fn averages_references() {
+        use rand::{rngs::StdRng, SeedableRng};
+        use super::{_lloyd, StoppingCriteria};
+        use crate::metric::Euclidean;
+
+        // A point that can only be averaged by reference, so `mean` being called at all is a bug
+        #[derive(Debug, Clone, PartialEq)]
+        struct RefsOnly(f64);
+
+        impl DataPoint for RefsOnly {
+            fn dist(&self, other: &Self) -> f64 {
+                (self.0 - other.0).abs()
+            }
+
+            fn mean(_: &[Self]) -> Option<Self> {
+                unreachable!("averaged by value")
+            }
+
+            fn mean_refs(ps: &[&Self]) -> Option<Self> {
+                f64::mean_refs(&ps.iter().map(|p| &p.0).collect::<Vec<_>>()).map(RefsOnly)
+            }
+        }
+
+        let points: Vec<RefsOnly> = [1.0, 2.0, 3.0, 10.0, 11.0, 12.0].iter().cloned().map(RefsOnly).collect();
+        let result = _lloyd(vec![RefsOnly(0.0), RefsOnly(5.0)], &points, StoppingCriteria::default(), &Euclidean);
+        assert_eq!(Cluster::centroids(&result.clusters), vec![RefsOnly(2.0), RefsOnly(11.0)]);
+        for seed in 0..10 {
+            let clusters = kmeans_with_rng(2, points.clone(), &mut StdRng::seed_from_u64(seed));
+            assert_eq!(clusters.len(), 2);
+        }
+    }
+
+    #[test]
+    fn converges_with_an_empty_cluster() {
+        use super::{_lloyd, StoppingCriteria};
+        use crate::metric::Euclidean;
+
+        let points = vec![(1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (8.0, 4.0), (8.0, 5.0)];
+
+        // Nothing is nearest the far-off centroid at first, so it's moved onto (1, 2), the point
+        // farthest from the other centroid, and the two groups end up split properly
+        let result = _lloyd(vec![(5.0, 3.0), (100.0, 100.0)], &points, StoppingCriteria::default(), &Euclidean);
+        assert!(result.converged);
+        assert_eq!(Cluster::centroids(&result.clusters), vec![(8.0, 4.5), (4.0 / 3.0, 7.0 / 3.0)]);
+        assert_eq!(result.labels, vec![1, 1, 1, 0, 0]);
+
+        // Integer points can't be averaged over nothing either
+        let points = vec![(1u8, 2u8), (1, 3), (2, 2), (8, 4), (8, 5)];
+        let result = _lloyd(vec![(5, 3), (0, 100), (100, 0)], &points, StoppingCriteria::default(), &Euclidean);
+        assert!(result.converged);
+        assert!(result.clusters.iter().all(|c| !c.points.is_empty()));
+
+        // With more clusters than points, the extras have to stay empty
+        let result = _lloyd(vec![(5, 3), (0, 100), (100, 0)], &points[..2], StoppingCriteria::default(), &Euclidean);
+        assert!(result.converged);
+        assert_eq!(result.clusters.iter().map(|c| c.points.len()).sum::<usize>(), 2);
+
+        // The duplicated points both go to the first of their two centroids, and one is moved
+        // back into the second, every round. Nothing actually changes, so it has converged.
+        let points = vec![(-1f64, -1f64), (-4.0, -3.0), (-4.0, -3.0), (-2.0, 4.0)];
+        for seed in 0..10 {
+            let clusters = kmeans_seeded(4, points.clone(), seed);
+            assert!(clusters.iter().all(|c| c.points.len() == 1));
+        }
+        let result = _lloyd(points.clone(), &points, StoppingCriteria::default(), &Euclidean);
+        assert!(result.converged);
+        assert_eq!(result.iterations, 1);
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn breaks_oscillation() {
+        use super::{_intermediate_inertia, _lloyd, _lloyd_observed, StoppingCriteria};
+        use crate::metric::Euclidean;
+
+        // With two centroids on the same spot, the duplicated point goes to the first and is
+        // moved straight back to the second every round, which is no change at all
+        let points = vec![(3u8, 3u8), (2, 1), (2, 1)];
+        let result = _lloyd(vec![(3, 3), (2, 1), (3, 3)], &points, StoppingCriteria::default(), &Euclidean);
+        assert!(result.converged);
+        assert!(result.iterations < 10);
+        assert!(result.clusters.iter().all(|c| c.points.len() == 1));
+
+        // Centroids rounded toward zero take turns owning (-1, 2), which goes around every four
+        // rounds as the two clusters swap places too. Of the two ways of splitting the points, the
+        // one with (-1, 2) in with (0, 2) and (-1, 1) is tighter.
+        let points = vec![(-1i8, 3i8), (3, -4), (0, 2), (-1, 1), (-1, 2)];
+        let mut inertias = vec![];
+        let (clusters, labels, iterations, converged) = _lloyd_observed(
+            vec![(-1, 3), (-1, 1), (-1, 1)], &points, StoppingCriteria::default(), &Euclidean, <(i8, i8)>::mean_refs,
+            &mut |_, clusters| inertias.push(_intermediate_inertia(clusters, &points, &Euclidean))
+        );
+        assert!(!converged);
+        assert!(iterations < 20);
+        assert_eq!(inertias.len(), iterations);
+        assert_eq!(_intermediate_inertia(&clusters, &points, &Euclidean), 2.0);
+        assert_eq!(inertias.last(), Some(&2.0));
+        assert!(inertias.iter().all(|inertia| *inertia >= 2.0));
+        assert_eq!(labels[2], labels[3]);
+        assert_eq!(labels[3], labels[4]);
+        assert_ne!(labels[0], labels[4]);
+
+        // A clustering that settles straight away isn't taken for a cycle
+        let points = vec![(-1f64, -1f64), (-4.0, -3.0), (-4.0, -3.0), (-2.0, 4.0)];
+        let result = kmeans_detailed(4, points.clone(), 1000, false);
+        assert!(result.converged);
+        assert_eq!(result.iterations, 1);
+        let result = KMeans::new(4).seed(0).fit(points);
+        assert!(result.converged);
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn every_variant_breaks_oscillation() {
+        use crate::{kmeans_dense, kmeans_elkan, weighted_kmeans};
+
+        // The points from `breaks_oscillation`, from many random starts
+        let points = vec![(-1i8, 3i8), (3, -4), (0, 2), (-1, 1), (-1, 2)];
+        for _ in 0..100 {
+            assert_eq!(kmeans(3, points.clone()).iter().map(|c| c.size()).sum::<usize>(), points.len());
+            assert_eq!(kmeans_elkan(3, points.clone()).iter().map(|c| c.size()).sum::<usize>(), points.len());
+            assert_eq!(kmeans_dense(3, points.clone()).iter().map(|c| c.size()).sum::<usize>(), points.len());
+            let weighted = weighted_kmeans(3, points.iter().map(|p| (*p, 1.0)).collect());
+            assert_eq!(weighted.iter().map(|c| c.size()).sum::<usize>(), points.len());
+        }
+
+        #[cfg(feature = "heapless")]
+        for _ in 0..100 {
+            let points = [(-1i8, 3i8), (3, -4), (0, 2), (-1, 1), (-1, 2)];
+            let fixed: [_; 3] = crate::kmeans_fixed(&points, &mut rand::thread_rng());
+            assert_eq!(fixed.iter().map(|c| c.size()).sum::<usize>(), points.len());
+        }
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn tolerance() {
+        use super::{_lloyd, StoppingCriteria};
+        use crate::metric::Euclidean;
+
+        // Starting both centroids at one end makes them creep along the line for a few rounds
+        let points: Vec<(f64, f64)> = (0..20).map(|x| (x as f64, 0.0)).collect();
+        let start = vec![(0.0, 0.0), (1.0, 0.0)];
+
+        let exact = _lloyd(start.clone(), &points, StoppingCriteria::default(), &Euclidean);
+        let loose = _lloyd(start, &points, StoppingCriteria { tol: 1.0, ..Default::default() }, &Euclidean);
+        assert!(loose.converged);
+        assert!(loose.iterations < exact.iterations);
+
+        assert_eq!(kmeans_with_tol(2, points, 1e-4).len(), 2);
+    }
+
+    #[test]
+    fn mean_of_nothing() {
+        assert_eq!(<(f64, f64)>::mean(&[]), None);
+        assert_eq!(<(u8, u8, u8)>::mean(&[]), None);
+        assert_eq!(<(i32, i32, i32, i32)>::mean(&[]), None);
+        assert_eq!(<(u8, u8)>::mean(&[(1, 2), (4, 7)]), Some((2, 4)));
+
+        // An empty cluster keeps its centroid instead of getting NaN or panicking
+        let mut cluster = Cluster::new(&(3u8, 4u8));
+        cluster.recalculate_centroid();
+        assert_eq!(cluster.centroid, (3, 4));
+    }
+
+    #[test]
+    fn recalculate_empty_cluster() {
+        // A point whose mean divides by the number of points without checking for zero
+        #[derive(Debug, Clone, PartialEq)]
+        struct Reading(f64);
+
+        impl DataPoint for Reading {
+            fn dist(&self, other: &Self) -> f64 {
+                (self.0 - other.0).abs()
+            }
+
+            fn mean(ps: &[Self]) -> Option<Self> {
+                Some(Reading(ps.iter().map(|p| p.0).sum::<f64>() / ps.len() as f64))
+            }
+        }
+
+        let mut cluster = Cluster::new(&Reading(2.5));
+        cluster.recalculate_centroid();
+        assert_eq!(cluster.centroid, Reading(2.5));
+
+        cluster.points = vec![Reading(1.0), Reading(2.0)];
+        cluster.recalculate_centroid();
+        assert_eq!(cluster.centroid, Reading(1.5));
+    }
+
+    #[test]
+    fn large_integer_means() {
+        // Adding these up in a `u64`/`i64` (or `usize`/`isize`) would overflow, and in an `f64`
+        // would round to a mean that doesn't fit back in the coordinate type
+        let points = vec![(u64::MAX, 1u64), (u64::MAX - 2, 3), (u64::MAX - 1, 2)];
+        assert_eq!(<(u64, u64)>::mean(&points), Some((u64::MAX - 1, 2)));
+
+        let points = vec![[i64::MIN, i64::MAX], [i64::MIN + 2, i64::MAX - 2]];
+        assert_eq!(<[i64; 2]>::mean(&points), Some([i64::MIN + 1, i64::MAX - 1]));
+
+        let points = vec![(usize::MAX, 0usize, 7usize); 1000];
+        assert_eq!(<(usize, usize, usize)>::mean(&points), Some((usize::MAX, 0, 7)));
+
+        // Truncation toward zero still applies to negative means
+        assert_eq!(<(i8, i8)>::mean(&[(-3, 3), (-4, 4)]), Some((-3, 3)));
+    }
+
+    #[test]
+    fn means_near_the_limits() {
+        assert_eq!(<(u8, u8)>::mean(&[(255, 254), (255, 255), (253, 255)]), Some((254, 254)));
+        assert_eq!(<(u8, u8)>::weighted_mean(&[(255, 254), (255, 255)], &[1.0, 3.0]), (255, 254));
+        assert_eq!(<(u8, u8)>::median(&[(255, 254), (255, 255)]), (255, 254));
+
+        // Extrapolating past either end clamps instead of panicking
+        assert_eq!(<[u8; 2]>::weighted_mean(&[[250, 5], [255, 0]], &[-1.0, 2.0]), [255, 0]);
+        assert_eq!(<[i8; 1]>::weighted_mean(&[[-128], [127]], &[2.0, -1.0]), [-128]);
+        assert_eq!(<[i8; 1]>::weighted_mean(&[[-128], [127]], &[-1.0, 2.0]), [127]);
+
+        // Weights that sum to zero give the origin, whatever the point type
+        assert_eq!(<(u8, u8)>::weighted_mean(&[(3, 4), (5, 6)], &[0.0, 0.0]), (0, 0));
+        assert_eq!(<[f64; 2]>::weighted_mean(&[[3.0, 4.0], [5.0, 6.0]], &[1.0, -1.0]), [0.0, 0.0]);
+        assert_eq!(f64::weighted_mean(&[3.0, 5.0], &[0.0, 0.0]), 0.0);
+        assert_eq!(Rgb::weighted_mean(&[Rgb(3, 4, 5)], &[0.0]), Rgb(0, 0, 0));
+        assert_eq!(Vec::<f64>::weighted_mean(&[vec![3.0, 4.0], vec![5.0, 6.0]], &[1.0, -1.0]), vec![0.0, 0.0]);
+        assert_eq!(Vec::<f64>::weighted_mean_refs(&[&vec![3.0, 4.0]], &[0.0]), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn vec_points() {
+        let a = vec![0.0, 0.0, 0.0, 0.0, 0.0];
+        let b = vec![1.0, 2.0, 2.0, 0.0, 4.0];
+        assert_eq!(a.dist(&b), 5.0);
+        assert_eq!(Vec::<f64>::mean(&[a, b]), Some(vec![0.5, 1.0, 1.0, 0.0, 2.0]));
+        assert_eq!(Vec::<f64>::mean(&[]), None);
+
+        let points: Vec<Vec<f64>> = vec![
+            vec![1.0, 2.0, 0.0, 0.0], vec![1.0, 3.0, 0.0, 0.0], vec![2.0, 2.0, 0.0, 0.0],
+            vec![8.0, 4.0, 1.0, 1.0], vec![8.0, 5.0, 1.0, 1.0], vec![9.0, 6.0, 1.0, 1.0]
+        ];
+        let clusters = kmeans_seeded(2, points, 3);
+        let mut centroids = Cluster::centroids(&clusters);
+        centroids.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(centroids, vec![vec![4.0 / 3.0, 7.0 / 3.0, 0.0, 0.0], vec![25.0 / 3.0, 5.0, 1.0, 1.0]]);
+    }
+
+    #[test]
+    fn array_points() {
+        let a = [0f64; 5];
+        let b = [1.0, 2.0, 2.0, 0.0, 4.0];
+        assert_eq!(a.dist(&b), 5.0);
+        assert_eq!(<[f64; 5]>::mean(&[a, b]), Some([0.5, 1.0, 1.0, 0.0, 2.0]));
+        assert_eq!(<[f64; 5]>::mean(&[]), None);
+        assert_eq!(<[f32; 2]>::mean(&[[1.0, 2.0], [3.0, 4.0]]), Some([2.0, 3.0]));
+
+        let points = vec![
+            [1.0, 2.0, 0.0, 0.0, 0.0], [1.0, 3.0, 0.0, 0.0, 0.0], [2.0, 2.0, 0.0, 0.0, 0.0],
+            [8.0, 4.0, 1.0, 1.0, 1.0], [8.0, 5.0, 1.0, 1.0, 1.0], [9.0, 6.0, 1.0, 1.0, 1.0]
+        ];
+        let clusters = kmeans_seeded(2, points, 3);
+        let mut centroids = Cluster::centroids(&clusters);
+        centroids.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(centroids, vec![[4.0 / 3.0, 7.0 / 3.0, 0.0, 0.0, 0.0], [25.0 / 3.0, 5.0, 1.0, 1.0, 1.0]]);
+    }
+
+    #[test]
+    fn medians() {
+        assert_eq!(<(f64, f64)>::median(&[(0.0, 4.0), (3.0, 2.0), (1.0, 0.0)]), (1.0, 2.0));
+        assert_eq!(<(f64, f64)>::median(&[(0.0, 0.0), (1.0, 2.0), (3.0, 4.0), (10.0, 10.0)]), (2.0, 3.0));
+        assert_eq!(<(u8, u8)>::median(&[(0, 0), (1, 2), (3, 5), (10, 10)]), (2, 3));
+        assert_eq!(Vec::<f64>::median(&[vec![5.0, 1.0], vec![1.0, 5.0], vec![3.0, 3.0]]), vec![3.0, 3.0]);
+
+        let mut cluster = Cluster::new(&(0f64, 0f64));
+        cluster.points = vec![(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (1.0, 1.0), (100.0, 0.0)];
+        cluster.recalculate_median();
+        assert_eq!(cluster.centroid, (1.0, 0.0));
+
+        // With no points, the centroid stays put rather than going to the origin
+        let mut cluster = Cluster::new(&(3f64, 4f64));
+        cluster.recalculate_median();
+        assert_eq!(cluster.centroid, (3.0, 4.0));
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn kmedians_ignores_outliers() {
+        let points = vec![(0f64, 0f64), (1.0, 0.0), (2.0, 0.0), (1.0, 1.0), (100.0, 0.0)];
+
+        // The outlier drags the mean far off to the side, but not the median
+        assert_eq!(Cluster::centroids(&kmeans(1, points.clone())), vec![(20.8, 0.2)]);
+        assert_eq!(Cluster::centroids(&kmedians(1, points)), vec![(1.0, 0.0)]);
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn clusters_an_iterator() {
+        // Two runs of points, far apart, straight from an iterator
+        let points = (0..).map(|i| if i % 2 == 0 { (i as f64 / 100.0, 0.0) } else { (100.0 + i as f64 / 100.0, 0.0) });
+        let mut clusters = kmeans_from_iter(2, points.take(20));
+        clusters.sort_by(|a, b| a.centroid.partial_cmp(&b.centroid).unwrap());
+        assert_eq!(clusters[0].points, (0..20).step_by(2).map(|i| (i as f64 / 100.0, 0.0)).collect::<Vec<_>>());
+        assert_eq!(clusters[1].size(), 10);
+
+        assert!(kmeans_from_iter(3, core::iter::empty::<(f64, f64)>()).is_empty());
+    }
+
+    #[cfg(feature = "thread_rng")]
+    #[test]
+    fn clusters_borrowed_points() {
+        let points: Vec<(f64, f64)> = (0..20).map(|i| (i as f64 / 100.0 + if i % 2 == 0 { 0.0 } else { 100.0 }, 0.0)).collect();
+        let mut clusters = kmeans_ref(2, &points);
+        clusters.sort_by(|a, b| a.centroid.partial_cmp(&b.centroid).unwrap());
+        // The points are still there, and each one was cloned into exactly one cluster
+        assert_eq!(clusters[0].points, points.iter().cloned().step_by(2).collect::<Vec<_>>());
+        assert_eq!(clusters[1].points, points.iter().cloned().skip(1).step_by(2).collect::<Vec<_>>());
+
+        assert!(kmeans_ref(3, &[] as &[(f64, f64)]).is_empty());
+    }
+
+    #[cfg(all(feature = "derive", feature = "std"))]
+    #[test]
+    fn derived_data_point() {
+        #[derive(Debug, Clone, PartialEq, DataPoint)]
+        struct Sample {
+            #[kmeans(skip)]
+            id: usize,
+            x: f64,
+            y: f64,
+            z: f64
+        }
+
+        let file = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/test_data.txt")).unwrap();
+        let data: Vec<(f64, f64, f64)> = file.trim().lines()
+            .map(|line| {
+                let coords: Vec<f64> = line.split_whitespace().map(|s| s.parse().unwrap()).collect();
+                (coords[0], coords[1], coords[2])
+            })
+            .collect();
+        let samples: Vec<Sample> = data.iter().enumerate()
+            .map(|(id, &(x, y, z))| Sample { id, x, y, z })
+            .collect();
+
+        for seed in 0..10 {
+            let tuples = kmeans_seeded(2, data.clone(), seed);
+            let derived = kmeans_seeded(2, samples.clone(), seed);
+            for (tuple, derived) in tuples.iter().zip(derived.iter()) {
+                assert_eq!((derived.centroid.x, derived.centroid.y, derived.centroid.z), tuple.centroid);
+                assert_eq!(derived.points.iter().map(|s| (s.x, s.y, s.z)).collect::<Vec<_>>(), tuple.points);
+                // The skipped id comes along from the first point
+                assert_eq!(derived.centroid.id, derived.points[0].id);
+            }
+        }
+
+        assert_eq!(Sample { id: 0, x: 0.0, y: 0.0, z: 0.0 }.dist(&Sample { id: 9, x: 2.0, y: 3.0, z: 6.0 }), 7.0);
+        assert_eq!(Sample::mean(&[]), None);
+
+        // Tuple structs and integer fields work too
+        #[derive(Debug, Clone, PartialEq, DataPoint)]
+        struct Pixel(u8, u8, #[kmeans(skip)] &'static str);
+        assert_eq!(Pixel::mean(&[Pixel(1, 10, "a"), Pixel(4, 13, "b")]), Some(Pixel(2, 11, "a")));
+        assert_eq!(Pixel(0, 0, "a").tie_order(&Pixel(0, 1, "b")), core::cmp::Ordering::Less);
     }
 
     #[cfg(feature = "std")]
@@ -194,7 +3299,7 @@ mod test {
         let clusters = kmeans(2, data.clone());
         let centroids = Cluster::centroids(&clusters);
 
-        assert!(centroids.contains(&(9.98514851485149, 9.76534653465346, 10.132673267326735)));
-        assert!(centroids.contains(&(2.5257425742574253, 2.5920792079207926, 2.7188118811881172)));
+        assert!(centroids.contains(&(9.985148514851485, 9.765346534653466, 10.132673267326732)));
+        assert!(centroids.contains(&(2.5257425742574258, 2.592079207920792, 2.718811881188119)));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,113 @@
+use std::fmt::{self, Display};
+use std::io::{self, BufRead, BufReader, Read};
+
+/// Something wrong with the data given to `load_points_from_reader`
+#[derive(Debug)]
+pub enum ParseError {
+    /// The data couldn't be read, or wasn't UTF-8
+    Io(io::Error),
+    /// A field on the given line (counting from 1) isn't a number
+    InvalidNumber { line: usize, field: String },
+    /// The point on the given line has a different number of coordinates than the ones before it
+    InconsistentDimensions { line: usize, expected: usize, found: usize }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "could not read points: {}", e),
+            ParseError::InvalidNumber { line, field } => write!(f, "line {}: {:?} is not a number", line, field),
+            ParseError::InconsistentDimensions { line, expected, found } =>
+                write!(f, "line {}: expected {} coordinates but found {}", line, expected, found)
+        }
+    }
+}
+
+impl std::error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ParseError::Io(e) => Some(e),
+            _ => None
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        ParseError::Io(e)
+    }
+}
+
+/// Reads one point per line from `reader`, with its coordinates separated by `delimiter`, as a
+/// `Vec<f64>` ready to pass to `kmeans`.
+///
+/// If `delimiter` is a space or a tab, any run of whitespace separates coordinates. Otherwise, as
+/// with `b','` for CSV, each field between delimiters is a coordinate, ignoring whitespace around
+/// it. Blank lines are skipped, and every other line has to have as many coordinates as the first.
+pub fn load_points_from_reader<R: Read>(reader: R, delimiter: u8) -> Result<Vec<Vec<f64>>, ParseError> {
+    let delimiter = char::from(delimiter);
+    let mut points: Vec<Vec<f64>> = vec![];
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let fields: Vec<&str> = if delimiter.is_ascii_whitespace() {
+            line.split_whitespace().collect()
+        } else {
+            line.split(delimiter).map(str::trim).collect()
+        };
+        let point = fields.iter()
+            .map(|field| field.parse::<f64>().map_err(|_| ParseError::InvalidNumber { line: i + 1, field: field.to_string() }))
+            .collect::<Result<Vec<f64>, ParseError>>()?;
+
+        if let Some(first) = points.first() {
+            if point.len() != first.len() {
+                return Err(ParseError::InconsistentDimensions { line: i + 1, expected: first.len(), found: point.len() });
+            }
+        }
+        points.push(point);
+    }
+    Ok(points)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use crate::kmeans;
+    use crate::types::Cluster;
+    use super::{load_points_from_reader, ParseError};
+
+    #[test]
+    fn loads_test_data() {
+        let file = File::open(concat!(env!("CARGO_MANIFEST_DIR"), "/test_data.txt")).unwrap();
+        let points = load_points_from_reader(file, b' ').unwrap();
+        assert_eq!(points.len(), 202);
+        assert_eq!(points[0], vec![3.7, 1.0, 3.8]);
+
+        let centroids = Cluster::centroids(&kmeans(2, points));
+        assert!(centroids.contains(&vec![9.985148514851485, 9.765346534653466, 10.132673267326732]));
+        assert!(centroids.contains(&vec![2.5257425742574258, 2.592079207920792, 2.718811881188119]));
+    }
+
+    #[test]
+    fn loads_csv() {
+        let csv = "1.5, 2\n\n-3,4e1\n";
+        assert_eq!(load_points_from_reader(csv.as_bytes(), b',').unwrap(), vec![vec![1.5, 2.0], vec![-3.0, 40.0]]);
+        assert!(load_points_from_reader("".as_bytes(), b',').unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        match load_points_from_reader("1 2\n3 x\n".as_bytes(), b' ') {
+            Err(ParseError::InvalidNumber { line: 2, field }) => assert_eq!(field, "x"),
+            other => panic!("unexpected {:?}", other)
+        }
+        match load_points_from_reader("1\t2\n\n3\t4\t5\n".as_bytes(), b'\t') {
+            Err(ParseError::InconsistentDimensions { line: 3, expected: 2, found: 3 }) => {}
+            other => panic!("unexpected {:?}", other)
+        }
+        assert!(matches!(load_points_from_reader(&[0xff, b'\n'][..], b' '), Err(ParseError::Io(_))));
+    }
+}
@@ -0,0 +1,137 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use rand::prelude::*;
+use crate::{_inverse_covariance, _kmeans, _nearest, StoppingCriteria};
+use crate::metric::{Euclidean, Mahalanobis, Metric};
+use crate::types::*;
+
+/// The most rounds `kmeans_mahalanobis` runs after the first, Euclidean, clustering
+const MAHALANOBIS_MAX_ITER: usize = 100;
+
+/// Clustering algorithm for clusters that are stretched out rather than round, where each cluster
+/// measures distance by its own `Mahalanobis` metric. In effect, this is a Gaussian mixture model
+/// where each point belongs wholly to one cluster.
+///
+/// It starts with plain k-means++. Then each round, each cluster is recentered on the mean of its
+/// points and fits the covariance of those points, and every point is reassigned to the cluster
+/// it's most likely to have come from. That's the one with the least squared Mahalanobis distance
+/// to its centroid, plus the log of the determinant of its covariance. Without that last term, a
+/// widely spread cluster would measure everything as close, and take points that are far outside
+/// it. Rounds continue until no point changes cluster.
+///
+/// Each covariance has a tiny amount added to its diagonal before it's inverted, so a cluster of
+/// points on a line, or a single point, still works. A cluster left with no points stays empty.
+pub fn kmeans_mahalanobis<P: DataPoint + Coordinates>(k: usize, datapoints: Vec<P>) -> Vec<Cluster<P>> {
+    _mahalanobis(k, datapoints, &mut thread_rng())
+}
+
+fn _mahalanobis<P, R>(k: usize, datapoints: Vec<P>, rng: &mut R) -> Vec<Cluster<P>>
+    where P: DataPoint + Coordinates, R: Rng + ?Sized
+{
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    let mut centroids = Cluster::centroids(&_kmeans(k, &datapoints, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &Euclidean, rng).clusters);
+    let mut labels: Vec<usize> = datapoints.iter().map(|p| _nearest(&centroids, p, &Euclidean)).collect();
+    for _ in 0..MAHALANOBIS_MAX_ITER {
+        // Each cluster's metric, and the log of its covariance's determinant
+        let mut shapes: Vec<Option<(Mahalanobis, f64)>> = Vec::with_capacity(centroids.len());
+        for (c, centroid) in centroids.iter_mut().enumerate() {
+            let members: Vec<P> = datapoints.iter().zip(labels.iter())
+                .filter(|(_, label)| **label == c)
+                .map(|(p, _)| p.clone())
+                .collect();
+            if let Some(mean) = P::mean(&members) {
+                *centroid = mean;
+            }
+            shapes.push(_inverse_covariance(&members).map(|(inverse_covariance, log_det)| (Mahalanobis { inverse_covariance }, log_det)));
+        }
+
+        let updated: Vec<usize> = datapoints.iter()
+            .map(|p| {
+                let mut best = (labels.len(), f64::INFINITY);
+                for (c, (centroid, shape)) in centroids.iter().zip(shapes.iter()).enumerate() {
+                    if let Some((metric, log_det)) = shape {
+                        let score = metric.distance_sq(p, centroid) + log_det;
+                        if score < best.1 {
+                            best = (c, score);
+                        }
+                    }
+                }
+                best.0
+            })
+            .collect();
+        // A point that can't be scored anywhere, for a NaN coordinate say, stays where it was
+        let updated: Vec<usize> = updated.into_iter().zip(labels.iter())
+            .map(|(new, old)| if new < centroids.len() { new } else { *old })
+            .collect();
+        if updated == labels {
+            break;
+        }
+        labels = updated;
+    }
+
+    let mut clusters: Vec<Cluster<P>> = centroids.iter().map(Cluster::new).collect();
+    for (point, label) in datapoints.into_iter().zip(labels) {
+        clusters[label].points.push(point);
+    }
+    clusters
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::kmeans_with_rng;
+    use crate::metric::{Mahalanobis, Metric};
+    use super::_mahalanobis;
+
+    #[test]
+    fn stretched_blobs() {
+        // Two long, thin bands, side by side but offset
+        let mut first = vec![];
+        let mut second = vec![];
+        for i in 0..41 {
+            let x = -10.0 + 0.5 * i as f64;
+            let wobble = if i % 2 == 0 { 0.3 } else { -0.3 };
+            first.push((x, wobble));
+            second.push((x + 12.0, 3.0 - wobble));
+        }
+        let points: Vec<(f64, f64)> = first.iter().chain(second.iter()).cloned().collect();
+        let split = |clusters: Vec<crate::types::Cluster<(f64, f64)>>| {
+            let mut groups: Vec<Vec<(f64, f64)>> = clusters.into_iter().map(|c| c.points).collect();
+            groups.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            groups
+        };
+
+        for seed in 0..10 {
+            // Round clusters cut the ends off the bands
+            let euclidean = split(kmeans_with_rng(2, points.clone(), &mut StdRng::seed_from_u64(seed)));
+            assert_ne!(euclidean, vec![first.clone(), second.clone()]);
+
+            let mahalanobis = split(_mahalanobis(2, points.clone(), &mut StdRng::seed_from_u64(seed)));
+            assert_eq!(mahalanobis, vec![first.clone(), second.clone()]);
+        }
+    }
+
+    #[test]
+    fn metric() {
+        // Spread out along x far more than along y
+        let points = vec![(-4.0, 0.0), (4.0, 0.0), (-4.0, 1.0), (4.0, 1.0)];
+        let metric = Mahalanobis::fit(&points).unwrap();
+        let along = metric.distance(&(0.0, 0.5), &(4.0, 0.5));
+        let across = metric.distance(&(0.0, 0.5), &(0.0, 1.0));
+        assert!((along - 1.0).abs() < 1e-6);
+        assert!((across - 1.0).abs() < 1e-5);
+        assert_eq!(metric.distance(&(1.0, 2.0), &(1.0, 2.0)), 0.0);
+
+        // All on one line, but still invertible
+        let line = Mahalanobis::fit(&[(0.0, 0.0), (1.0, 0.0), (2.0, 0.0)]).unwrap();
+        assert!(line.distance(&(0.0, 0.0), &(0.0, 1.0)) > 100.0);
+        assert_eq!(Mahalanobis::fit::<(f64, f64)>(&[]), None);
+    }
+}
@@ -0,0 +1,357 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+#[cfg(any(feature = "std", feature = "alloc"))]
+use core::cmp::Ordering;
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::types::{Field, MixedPoint};
+use crate::types::{Coordinates, DataPoint};
+
+/// A way of measuring the distance between two points.
+pub trait Metric<P> {
+    /// Calculates the distance between two points
+    fn distance(&self, a: &P, b: &P) -> f64;
+
+    /// Calculates the squared distance between two points.
+    ///
+    /// Like `DataPoint::dist_sq`, this is what the clustering uses to compare distances.
+    fn distance_sq(&self, a: &P, b: &P) -> f64 {
+        self.distance(a, b).powi(2)
+    }
+}
+
+/// The straight-line distance, as given by `DataPoint::dist`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Euclidean;
+
+impl<P: DataPoint> Metric<P> for Euclidean {
+    fn distance(&self, a: &P, b: &P) -> f64 {
+        a.dist(b)
+    }
+
+    fn distance_sq(&self, a: &P, b: &P) -> f64 {
+        a.dist_sq(b)
+    }
+}
+
+/// The L1 or "taxicab" distance, the sum of the absolute differences along each axis.
+///
+/// The point that minimizes the total L1 distance to a set of points is their median rather than
+/// their mean, so this pairs best with a median-based recenter as in k-medians.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Manhattan;
+
+impl<P: Coordinates> Metric<P> for Manhattan {
+    fn distance(&self, a: &P, b: &P) -> f64 {
+        (0..a.dimensions())
+            .map(|i| (a.coordinate(i) - b.coordinate(i)).abs())
+            .sum()
+    }
+}
+
+/// The L∞ or "chessboard" distance, the largest absolute difference along any one axis.
+///
+/// The point that minimizes the largest L∞ distance to a set of points isn't their mean, but
+/// `kmeans_with_metric` still recenters on the mean, so with this metric that's an approximation.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Chebyshev;
+
+impl<P: Coordinates> Metric<P> for Chebyshev {
+    fn distance(&self, a: &P, b: &P) -> f64 {
+        (0..a.dimensions())
+            .map(|i| (a.coordinate(i) - b.coordinate(i)).abs())
+            .fold(0.0, f64::max)
+    }
+}
+
+/// The Lp distance, `(Σ |a_i - b_i|^p)^(1/p)`, which is `Manhattan` when `p` is `1.0`, `Euclidean`
+/// when it's `2.0`, and `Chebyshev` when it's `f64::INFINITY`.
+///
+/// `p` should be at least `1.0`; below that this isn't a true distance, as the triangle
+/// inequality doesn't hold. As with `Chebyshev`, recentering on the mean is only an approximation
+/// unless `p` is `2.0`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Minkowski {
+    pub p: f64,
+}
+
+impl<P: Coordinates> Metric<P> for Minkowski {
+    fn distance(&self, a: &P, b: &P) -> f64 {
+        if self.p == f64::INFINITY {
+            return Chebyshev.distance(a, b);
+        }
+        if self.p == 2.0 {
+            return self.distance_sq(a, b).sqrt();
+        }
+        (0..a.dimensions())
+            .map(|i| (a.coordinate(i) - b.coordinate(i)).abs().powf(self.p))
+            .sum::<f64>()
+            .powf(self.p.recip())
+    }
+
+    fn distance_sq(&self, a: &P, b: &P) -> f64 {
+        if self.p == 2.0 {
+            return (0..a.dimensions())
+                .map(|i| (a.coordinate(i) - b.coordinate(i)).powi(2))
+                .sum();
+        }
+        self.distance(a, b).powi(2)
+    }
+}
+
+/// The great-circle distance between two `(latitude, longitude)` points given in degrees, on a
+/// sphere of the given `radius`. The distance comes out in whatever unit `radius` is in, so
+/// `Haversine::default()` measures in kilometers on the Earth.
+///
+/// The mean of some latitudes and longitudes is only roughly the center of the points, and is
+/// badly wrong for points on either side of the 180th meridian, so `kmeans_with_metric` with this
+/// metric is an approximation. `kmeans_geographic` recenters on the true mean direction instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Haversine {
+    pub radius: f64,
+}
+
+impl Haversine {
+    /// The Earth's mean radius, in kilometers
+    pub const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+    /// Distances in kilometers on the Earth
+    pub fn kilometers() -> Self {
+        Self { radius: Self::EARTH_RADIUS_KM }
+    }
+
+    /// Distances in meters on the Earth
+    pub fn meters() -> Self {
+        Self { radius: Self::EARTH_RADIUS_KM * 1000.0 }
+    }
+}
+
+impl Default for Haversine {
+    fn default() -> Self {
+        Self::kilometers()
+    }
+}
+
+impl Metric<(f64, f64)> for Haversine {
+    fn distance(&self, a: &(f64, f64), b: &(f64, f64)) -> f64 {
+        let (lat_a, lat_b) = (a.0.to_radians(), b.0.to_radians());
+        let (d_lat, d_lon) = ((b.0 - a.0).to_radians(), (b.1 - a.1).to_radians());
+        let h = (d_lat / 2.0).sin().powi(2) + lat_a.cos() * lat_b.cos() * (d_lon / 2.0).sin().powi(2);
+        2.0 * self.radius * h.clamp(0.0, 1.0).sqrt().asin()
+    }
+}
+
+/// One minus the cosine of the angle between two points, so that points in the same direction are
+/// `0.0` apart and points in opposite directions are `2.0` apart, however long they are.
+///
+/// A point at the origin has no direction, so it's treated as being as far as possible (`2.0`)
+/// from everything.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Cosine;
+
+impl<P: Coordinates> Metric<P> for Cosine {
+    fn distance(&self, a: &P, b: &P) -> f64 {
+        let (mut dot, mut norm_a, mut norm_b) = (0.0, 0.0, 0.0);
+        for i in 0..a.dimensions() {
+            let (x, y) = (a.coordinate(i), b.coordinate(i));
+            dot += x * y;
+            norm_a += x * x;
+            norm_b += y * y;
+        }
+
+        if norm_a == 0.0 || norm_b == 0.0 {
+            return 2.0;
+        }
+        1.0 - dot / f64::sqrt(norm_a * norm_b)
+    }
+}
+
+/// The [Jaccard distance] between two sets, `1 - |A ∩ B| / |A ∪ B|`: `0.0` for the same set and
+/// `1.0` for sets with nothing in common. Two empty sets are `0.0` apart.
+///
+/// Sets are `Vec`s of items, such as tag ids, which must be sorted and without duplicates. Sets have
+/// no mean, so this pairs with `kmedoids_with_metric`, which only ever centers clusters on one of
+/// the sets being clustered.
+///
+/// [Jaccard distance]: https://en.wikipedia.org/wiki/Jaccard_index
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Jaccard;
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<T: Ord> Metric<Vec<T>> for Jaccard {
+    fn distance(&self, a: &Vec<T>, b: &Vec<T>) -> f64 {
+        debug_assert!(a.windows(2).all(|w| w[0] < w[1]), "set is not sorted and deduplicated");
+        debug_assert!(b.windows(2).all(|w| w[0] < w[1]), "set is not sorted and deduplicated");
+
+        // Walk both sets in step, like merging them, counting what they share
+        let (mut i, mut j, mut shared) = (0, 0, 0);
+        while i < a.len() && j < b.len() {
+            match a[i].cmp(&b[j]) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    shared += 1;
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+
+        let union = a.len() + b.len() - shared;
+        if union == 0 {
+            return 0.0;
+        }
+        1.0 - shared as f64 / union as f64
+    }
+}
+
+/// The [Mahalanobis distance], `sqrt((a - b)ᵀ S⁻¹ (a - b))` for a covariance matrix `S`: the
+/// straight-line distance after stretching space so the points the covariance came from are
+/// spread out evenly in every direction. Points along a direction the data varies a lot in are
+/// closer than they look, and points across one it hardly varies in are farther.
+///
+/// `kmeans_mahalanobis` fits one of these to each cluster as it goes, for clusters that are
+/// stretched out rather than round.
+///
+/// [Mahalanobis distance]: https://en.wikipedia.org/wiki/Mahalanobis_distance
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mahalanobis {
+    /// `S⁻¹`, with as many rows and columns as the points have dimensions
+    pub inverse_covariance: Vec<Vec<f64>>,
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Mahalanobis {
+    /// Measures distances by the covariance of `points`. A tiny amount is added to the diagonal
+    /// of the covariance first, so that points all on a line or plane still give a distance,
+    /// albeit a very large one off that line or plane. `None` if there are no points or a
+    /// coordinate is NaN.
+    pub fn fit<P: Coordinates>(points: &[P]) -> Option<Self> {
+        crate::_inverse_covariance(points).map(|(inverse_covariance, _)| Mahalanobis { inverse_covariance })
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<P: Coordinates> Metric<P> for Mahalanobis {
+    fn distance(&self, a: &P, b: &P) -> f64 {
+        self.distance_sq(a, b).sqrt()
+    }
+
+    fn distance_sq(&self, a: &P, b: &P) -> f64 {
+        let diff: Vec<f64> = (0..a.dimensions()).map(|i| a.coordinate(i) - b.coordinate(i)).collect();
+        self.inverse_covariance.iter().zip(diff.iter())
+            .map(|(row, x)| x * row.iter().zip(diff.iter()).map(|(s, y)| s * y).sum::<f64>())
+            .sum::<f64>()
+            // Rounding can take the distance from a point to itself a hair below zero
+            .max(0.0)
+    }
+}
+
+/// The [dynamic time warping] distance between two sequences, which may be of different lengths:
+/// the least total absolute difference between them, over every way of lining them up that keeps
+/// both in order and pairs each value with at least one value of the other. A sequence stretched
+/// or squeezed in time is close to the original, where a pointwise distance would find them far
+/// apart.
+///
+/// The full cost matrix is filled in, so measuring sequences of lengths `n` and `m` takes `O(nm)`
+/// time. An empty sequence is `0.0` from another empty one and infinitely far from anything else.
+/// There's no simple mean of sequences under this distance, so this pairs with
+/// `kmedoids_with_metric`, which centers each cluster on one of its sequences.
+///
+/// [dynamic time warping]: https://en.wikipedia.org/wiki/Dynamic_time_warping
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DynamicTimeWarping;
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Metric<Vec<f64>> for DynamicTimeWarping {
+    fn distance(&self, a: &Vec<f64>, b: &Vec<f64>) -> f64 {
+        if a.is_empty() || b.is_empty() {
+            return if a.len() == b.len() { 0.0 } else { f64::INFINITY };
+        }
+
+        // Only the previous row of the cost matrix is needed to fill in the next. `previous[j]` is
+        // the cost of the best alignment of `a[..i]` with `b[..j]`, where lining up nothing with
+        // nothing costs nothing and nothing with something is impossible.
+        let mut previous = vec![f64::INFINITY; b.len() + 1];
+        previous[0] = 0.0;
+        let mut current = vec![f64::INFINITY; b.len() + 1];
+        for x in a.iter() {
+            current[0] = f64::INFINITY;
+            for (j, y) in b.iter().enumerate() {
+                let best = previous[j].min(previous[j + 1]).min(current[j]);
+                current[j + 1] = (x - y).abs() + best;
+            }
+            core::mem::swap(&mut previous, &mut current);
+        }
+        previous[b.len()]
+    }
+}
+
+/// The [Gower distance] between two `MixedPoint`s: the mean over their fields of how different
+/// each field is, from `0.0` for the same to `1.0` for as different as can be.
+///
+/// Two categories are either the same or entirely different. Two numbers differ by the gap
+/// between them as a share of the field's range, `ranges[i]`, which should be how far apart the
+/// smallest and largest values of field `i` are across all the points, as `Gower::fit` finds it.
+/// A field with no range given is taken to span `1.0`, so `Gower::default()` suits points already
+/// scaled to `0.0..=1.0`. Numbers in a field whose range is zero are compared like categories, and
+/// a number is entirely different from a category.
+///
+/// [Gower distance]: https://doi.org/10.2307/2528823
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Gower {
+    pub ranges: Vec<f64>,
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Gower {
+    /// Measures the range of each numeric field across `points`, so each counts for as much as
+    /// the others however it's scaled. Categorical fields are given a range of zero, which is never
+    /// used.
+    pub fn fit(points: &[MixedPoint]) -> Self {
+        let fields = points.first().map_or(0, |p| p.0.len());
+        let ranges = (0..fields)
+            .map(|i| {
+                let values = points.iter().filter_map(|p| match p.0[i] {
+                    Field::Numeric(x) => Some(x),
+                    Field::Categorical(_) => None
+                });
+                let (min, max) = values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| (min.min(x), max.max(x)));
+                if min <= max { max - min } else { 0.0 }
+            })
+            .collect();
+        Gower { ranges }
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Metric<MixedPoint> for Gower {
+    fn distance(&self, a: &MixedPoint, b: &MixedPoint) -> f64 {
+        debug_assert_eq!(a.0.len(), b.0.len(), "points have different numbers of fields");
+        if a.0.is_empty() {
+            return 0.0;
+        }
+
+        let total: f64 = a.0.iter().zip(b.0.iter()).enumerate()
+            .map(|(i, fields)| match fields {
+                (Field::Numeric(x), Field::Numeric(y)) => {
+                    let range = self.ranges.get(i).copied().unwrap_or(1.0);
+                    if range > 0.0 {
+                        (x - y).abs() / range
+                    } else if x == y {
+                        0.0
+                    } else {
+                        1.0
+                    }
+                }
+                (Field::Categorical(x), Field::Categorical(y)) if x == y => 0.0,
+                _ => 1.0
+            })
+            .sum();
+        total / a.0.len() as f64
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use rand::prelude::*;
+use crate::{_initialize_clusters, _nearest};
+use crate::metric::Euclidean;
+use crate::types::*;
+
+/// Clustering algorithm using k-means++ and [Sculley's mini-batch] updates.
+///
+/// Instead of assigning every point on every round, each of the `max_iter` rounds samples
+/// `batch_size` of the `datapoints` and nudges the nearest centroid toward each one. A centroid's
+/// learning rate is one over the number of points it has been nudged toward so far, so centroids
+/// settle down as they see more data. The result is usually a little worse than `kmeans`, but each
+/// round costs `batch_size` rather than `datapoints.len()` distance calculations.
+///
+/// Once the centroids are found, every point is assigned to its nearest one to build the returned
+/// clusters.
+///
+/// [Sculley's mini-batch]: https://www.eecs.tufts.edu/~dsculley/papers/fastkmeans.pdf
+pub fn minibatch_kmeans<P: WeightedMean>(k: usize, datapoints: Vec<P>, batch_size: usize, max_iter: usize) -> Vec<Cluster<P>> {
+    _minibatch(k, datapoints, batch_size, max_iter, &mut thread_rng())
+}
+
+fn _minibatch<P, R>(k: usize, datapoints: Vec<P>, batch_size: usize, max_iter: usize, rng: &mut R) -> Vec<Cluster<P>>
+    where P: WeightedMean, R: Rng + ?Sized
+{
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    let mut centroids: Vec<P> = _initialize_clusters(k, &datapoints, InitMethod::KMeansPlusPlus, &Euclidean, rng)
+        .into_iter()
+        .map(Cluster::into_centroids)
+        .collect();
+    let mut counts = vec![0usize; k];
+
+    for _ in 0..max_iter {
+        // Assign the whole batch against the same centroids before moving any of them
+        let batch: Vec<(&P, usize)> = datapoints.choose_multiple(rng, batch_size)
+            .map(|point| (point, _nearest(&centroids, point, &Euclidean)))
+            .collect();
+
+        for (point, c) in batch {
+            counts[c] += 1;
+            let rate = 1.0 / counts[c] as f64;
+            centroids[c] = P::weighted_mean(&[centroids[c].clone(), point.clone()], &[1.0 - rate, rate]);
+        }
+    }
+
+    let mut clusters: Vec<Cluster<P>> = centroids.iter().map(Cluster::new).collect();
+    for point in datapoints {
+        let c = _nearest(&centroids, &point, &Euclidean);
+        clusters[c].points.push(point);
+    }
+    clusters
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::types::{Cluster, DataPoint};
+    use super::_minibatch;
+
+    #[test]
+    fn near_full_batch() {
+        let points = vec![
+            (1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (2.0, 3.0), (2.0, 4.0),
+            (3.0, 1.0), (3.0, 2.0), (3.0, 3.0), (3.0, 4.0), (4.0, 1.0), (4.0, 2.0), (4.0, 3.0),
+            (4.0, 4.0), (5.0, 2.0), (5.0, 3.0),
+            (6.0, 5.0), (6.0, 6.0), (6.0, 7.0), (7.0, 5.0), (7.0, 6.0), (7.0, 7.0), (7.0, 8.0),
+            (8.0, 4.0), (8.0, 5.0), (8.0, 6.0), (8.0, 7.0), (8.0, 8.0), (9.0, 5.0), (9.0, 6.0),
+            (9.0, 7.0), (9.0, 8.0), (10.0, 6.0), (10.0, 7.0)
+        ];
+        let full_batch = [(46.0 / 15.0, 13.0 / 5.0), (71.0 / 9.0, 113.0 / 18.0)];
+
+        for seed in 0..10 {
+            let clusters = _minibatch(2, points.clone(), 8, 100, &mut StdRng::seed_from_u64(seed));
+
+            assert_eq!(clusters.iter().map(|c| c.points.len()).sum::<usize>(), points.len());
+            for centroid in Cluster::centroids(&clusters) {
+                assert!(full_batch.iter().any(|c| c.dist(&centroid) < 0.5));
+            }
+        }
+    }
+}
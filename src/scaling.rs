@@ -0,0 +1,150 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+use crate::types::CompensatedSum;
+
+/// The per-dimension means and standard deviations of a set of points, for rescaling them (and any
+/// points that come after them) to a mean of zero and a standard deviation of one in every
+/// dimension.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standardization {
+    pub means: Vec<f64>,
+    pub std_devs: Vec<f64>
+}
+
+impl Standardization {
+    /// Finds the mean and (population) standard deviation of each dimension of `points`. The
+    /// points are all taken to have as many dimensions as the first.
+    pub fn fit(points: &[Vec<f64>]) -> Self {
+        let means = _dimension_means(points, |x| x);
+        let std_devs = _dimension_means(points, |x| x * x).iter().zip(means.iter())
+            // Rounding can leave the variance of a constant dimension a hair below zero
+            .map(|(mean_sq, mean)| (mean_sq - mean * mean).max(0.0).sqrt())
+            .collect();
+        Standardization { means, std_devs }
+    }
+
+    /// Subtracts the fitted mean from each coordinate of `point` and divides by the fitted
+    /// standard deviation. A dimension with a standard deviation of zero is left unchanged.
+    pub fn apply(&self, point: &mut [f64]) {
+        for ((x, mean), std_dev) in point.iter_mut().zip(self.means.iter()).zip(self.std_devs.iter()) {
+            if *std_dev > 0.0 {
+                *x = (*x - mean) / std_dev;
+            }
+        }
+    }
+}
+
+/// The per-dimension minimums and maximums of a set of points, for rescaling them (and any points
+/// that come after them) so that every dimension runs from zero to one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MinMaxScaling {
+    pub mins: Vec<f64>,
+    pub maxes: Vec<f64>
+}
+
+impl MinMaxScaling {
+    /// Finds the smallest and largest value of each dimension of `points`. The points are all
+    /// taken to have as many dimensions as the first.
+    pub fn fit(points: &[Vec<f64>]) -> Self {
+        let dimensions = points.first().map_or(0, Vec::len);
+        let mins = (0..dimensions).map(|i| points.iter().map(|p| p[i]).fold(f64::INFINITY, f64::min)).collect();
+        let maxes = (0..dimensions).map(|i| points.iter().map(|p| p[i]).fold(f64::NEG_INFINITY, f64::max)).collect();
+        MinMaxScaling { mins, maxes }
+    }
+
+    /// Maps each coordinate of `point` from the fitted range to `0.0..=1.0`. Points that weren't
+    /// fitted can fall outside it. A dimension whose values were all the same is left unchanged.
+    pub fn apply(&self, point: &mut [f64]) {
+        for ((x, min), max) in point.iter_mut().zip(self.mins.iter()).zip(self.maxes.iter()) {
+            if max > min {
+                *x = (*x - min) / (max - min);
+            }
+        }
+    }
+}
+
+/// Rescales each dimension of `points` to a mean of zero and a standard deviation of one, as
+/// k-means weighs every dimension by its scale. Returns the fitted `Standardization`, to rescale
+/// new points the same way before `predict`ing their clusters.
+pub fn standardize(points: &mut [Vec<f64>]) -> Standardization {
+    let standardization = Standardization::fit(points);
+    for point in points.iter_mut() {
+        standardization.apply(point);
+    }
+    standardization
+}
+
+/// Rescales each dimension of `points` to run from zero to one. Returns the fitted
+/// `MinMaxScaling`, to rescale new points the same way before `predict`ing their clusters.
+pub fn minmax_scale(points: &mut [Vec<f64>]) -> MinMaxScaling {
+    let scaling = MinMaxScaling::fit(points);
+    for point in points.iter_mut() {
+        scaling.apply(point);
+    }
+    scaling
+}
+
+/// The mean of `f` of each dimension of `points`
+fn _dimension_means(points: &[Vec<f64>], f: fn(f64) -> f64) -> Vec<f64> {
+    let dimensions = points.first().map_or(0, Vec::len);
+    (0..dimensions)
+        .map(|i| {
+            let sum = points.iter().fold(CompensatedSum::default(), |sum, p| sum + f(p[i]));
+            sum.total() / points.len() as f64
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use super::{minmax_scale, standardize, MinMaxScaling, Standardization};
+
+    fn points() -> Vec<Vec<f64>> {
+        vec![
+            vec![1.0, 200.0, 5.0], vec![2.0, 400.0, 5.0], vec![4.0, 100.0, 5.0],
+            vec![8.0, 300.0, 5.0], vec![3.0, 900.0, 5.0]
+        ]
+    }
+
+    #[test]
+    fn standardized() {
+        let mut points = points();
+        let standardization = standardize(&mut points);
+        assert_eq!(standardization, Standardization::fit(&self::points()));
+
+        for i in 0..2 {
+            let mean = points.iter().map(|p| p[i]).sum::<f64>() / 5.0;
+            let variance = points.iter().map(|p| (p[i] - mean).powi(2)).sum::<f64>() / 5.0;
+            assert!(mean.abs() < 1e-12);
+            assert!((variance.sqrt() - 1.0).abs() < 1e-12);
+        }
+        // Every point has the same third coordinate, so there's nothing to scale it by
+        assert!(points.iter().all(|p| p[2] == 5.0));
+
+        let mut new_point = vec![3.6, 380.0, 7.0];
+        standardization.apply(&mut new_point);
+        assert!(new_point[0].abs() < 1e-12 && new_point[1].abs() < 1e-12);
+        assert_eq!(new_point[2], 7.0);
+    }
+
+    #[test]
+    fn minmax_scaled() {
+        let mut points = points();
+        let scaling = minmax_scale(&mut points);
+        assert_eq!(scaling, MinMaxScaling { mins: vec![1.0, 100.0, 5.0], maxes: vec![8.0, 900.0, 5.0] });
+        assert_eq!(points[3], vec![1.0, 0.25, 5.0]);
+        assert_eq!(points[2], vec![3.0 / 7.0, 0.0, 5.0]);
+
+        let mut new_point = vec![15.0, 500.0, 6.0];
+        scaling.apply(&mut new_point);
+        assert_eq!(new_point, vec![2.0, 0.5, 6.0]);
+
+        assert!(minmax_scale(&mut []).mins.is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use rand::prelude::*;
+use crate::{_initialize_clusters, _lloyd_with_center, StoppingCriteria};
+use crate::metric::Cosine;
+use crate::types::*;
+
+/// Clustering algorithm using k-means++ and the cosine distance, for points where only the
+/// direction matters (like TF-IDF vectors).
+///
+/// Each point is scaled to unit length before clustering, and each new centroid is the mean of its
+/// cluster scaled back to unit length. Points at the origin have no direction, so they're left as
+/// they are, aren't used as starting centroids, and end up in whichever cluster has the smallest
+/// centroid by `DataPoint::tie_order`, since they're as close to one as to any other.
+///
+/// The returned centroids are unit length, but the clusters hold the `datapoints` as they were
+/// given.
+pub fn spherical_kmeans<P: Normalize + Coordinates>(k: usize, datapoints: Vec<P>) -> Vec<Cluster<P>> {
+    _spherical(k, datapoints, &mut thread_rng())
+}
+
+fn _spherical<P, R>(k: usize, datapoints: Vec<P>, rng: &mut R) -> Vec<Cluster<P>>
+    where P: Normalize + Coordinates, R: Rng + ?Sized
+{
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    let normalized: Vec<P> = datapoints.iter().map(P::normalized).collect();
+
+    // A point at the origin would make a centroid that nothing else can join, so only seed from
+    // those with a direction, if there are enough of them
+    let mut seeds: Vec<P> = normalized.iter()
+        .filter(|p| (0..p.dimensions()).any(|i| p.coordinate(i) != 0.0))
+        .cloned()
+        .collect();
+    if seeds.len() < k {
+        seeds = normalized.clone();
+    }
+
+    let centroids = _initialize_clusters(k, &seeds, InitMethod::KMeansPlusPlus, &Cosine, rng)
+        .into_iter()
+        .map(Cluster::into_centroids)
+        .collect();
+    let result = _lloyd_with_center(
+        centroids,
+        &normalized,
+        StoppingCriteria::default(),
+        &Cosine,
+        |ps| P::mean_refs(ps).map(|mean| mean.normalized())
+    );
+
+    let mut clusters: Vec<Cluster<P>> = result.clusters.iter().map(|c| Cluster::new(&c.centroid)).collect();
+    for (point, label) in datapoints.into_iter().zip(result.labels) {
+        clusters[label].points.push(point);
+    }
+    clusters
+}
+
+#[cfg(test)]
+mod test {
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::metric::{Cosine, Metric};
+    use crate::types::{Cluster, DataPoint};
+    use super::_spherical;
+
+    #[test]
+    fn clusters_by_direction() {
+        let flat = vec![(1f64, 0.1f64), (10.0, 1.2), (50.0, 4.0), (0.5, 0.06)];
+        let steep = vec![(0.1f64, 1f64), (1.1, 10.0), (5.0, 50.0), (0.05, 0.5)];
+        let points: Vec<(f64, f64)> = flat.iter().chain(steep.iter()).cloned().collect();
+
+        for seed in 0..10 {
+            let mut clusters = _spherical(2, points.clone(), &mut StdRng::seed_from_u64(seed));
+            clusters.sort_by(|a, b| b.centroid.partial_cmp(&a.centroid).unwrap());
+
+            // Plain k-means would split these by length instead
+            assert_eq!(clusters[0].points, flat);
+            assert_eq!(clusters[1].points, steep);
+            for centroid in Cluster::centroids(&clusters) {
+                assert!((centroid.dist(&(0.0, 0.0)) - 1.0).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn zero_vectors() {
+        let points = vec![(0f64, 0f64), (1.0, 0.0), (2.0, 0.1), (0.0, 1.0), (0.1, 2.0)];
+
+        for seed in 0..10 {
+            let clusters = _spherical(2, points.clone(), &mut StdRng::seed_from_u64(seed));
+            assert_eq!(clusters.iter().map(|c| c.points.len()).sum::<usize>(), points.len());
+            for cluster in clusters.iter() {
+                assert!(!cluster.centroid.0.is_nan() && !cluster.centroid.1.is_nan());
+                assert!(Cosine.distance(&cluster.centroid, &(1.0, 1.0)) < 1.0);
+            }
+        }
+    }
+}
@@ -1,31 +1,187 @@
 #[cfg(feature = "alloc")]
 use alloc::vec::Vec;
 
-use core::iter::Sum;
-use core::convert::TryFrom;
-use core::fmt::Debug;
+use num_traits::{FromPrimitive, ToPrimitive};
+#[cfg(not(feature = "std"))]
+use num_traits::Float;
+use core::cmp::Ordering;
+use core::fmt::{self, Debug, Display};
+use core::ops::Add;
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+use crate::metric::{Gower, Metric};
+
+/// Derives `DataPoint` for a struct of numeric fields, with the `derive` feature
+#[cfg(feature = "derive")]
+pub use kmeans_derive::DataPoint;
 
 /// A representation of a point in a data space.
 pub trait DataPoint: Clone + PartialEq {
     /// Calculates the distance between two points
     fn dist(&self, other: &Self) -> f64;
 
-    /// Calculates the mean of a slice of points
-    fn mean(ps: &[Self]) -> Self;
+    /// Calculates the squared distance between two points.
+    ///
+    /// This is what the clustering uses to compare distances, so implementations that can skip the
+    /// square root in `dist` should override it.
+    fn dist_sq(&self, other: &Self) -> f64 {
+        self.dist(other).powi(2)
+    }
+
+    /// Calculates the mean of a slice of points, or `None` if there are no points to average
+    fn mean(ps: &[Self]) -> Option<Self>;
+
+    /// Calculates the mean of some borrowed points, as `mean` does, for points scattered about
+    /// that would otherwise have to be cloned into a slice.
+    ///
+    /// By default, this clones them into a slice anyway. The built-in point types average the
+    /// references directly.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn mean_refs(ps: &[&Self]) -> Option<Self> {
+        let owned: Vec<Self> = ps.iter().map(|p| (*p).clone()).collect();
+        Self::mean(&owned)
+    }
+
+    /// Orders two centroids for when a point is exactly as close to both: it's assigned to the
+    /// smaller one. That way which cluster gets it doesn't depend on the order the clusters are in,
+    /// which is random.
+    ///
+    /// The built-in point types compare their coordinates in order, like words in a dictionary.
+    /// By default, all points are equal, and a tie goes to whichever cluster comes first.
+    fn tie_order(&self, _other: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+/// A point that can be averaged with some points counting for more than others
+pub trait WeightedMean: DataPoint {
+    /// Calculates the mean of a slice of points, where `weights[i]` is how much `ps[i]` counts.
+    ///
+    /// If the weights sum to zero, there's no telling where the mean should be, and it's the
+    /// origin, every coordinate zero. The built-in point types all do this.
+    fn weighted_mean(ps: &[Self], weights: &[f64]) -> Self;
+
+    /// Calculates the weighted mean of some borrowed points, as `weighted_mean` does, for points
+    /// scattered about that would otherwise have to be cloned into a slice.
+    ///
+    /// By default, this clones them into a slice anyway. The built-in point types average the
+    /// references directly.
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn weighted_mean_refs(ps: &[&Self], weights: &[f64]) -> Self {
+        let owned: Vec<Self> = ps.iter().map(|p| (*p).clone()).collect();
+        Self::weighted_mean(&owned, weights)
+    }
+}
+
+/// A point for which a median of several points can be found
+pub trait Median: DataPoint {
+    /// Calculates the per-coordinate median of a slice of points, without reordering them
+    fn median(ps: &[Self]) -> Self;
 }
 
-/// A clustering of `points` around a `centroid`
+/// A point that can be broken down into its position along each axis, for metrics that need
+/// more than `DataPoint::dist`.
+pub trait Coordinates {
+    /// The number of axes the point has
+    fn dimensions(&self) -> usize;
+
+    /// The point's position along axis `i`
+    fn coordinate(&self, i: usize) -> f64;
+}
+
+/// A point that can be scaled to unit length, for clustering by direction alone
+pub trait Normalize: DataPoint {
+    /// Scales the point to have an L2 norm of one. A point at the origin has no direction, so it's
+    /// returned as it is.
+    fn normalized(&self) -> Self;
+}
+
+/// A point with a counterpart whose coordinates are all `f64`s, for clustering integer points
+/// around centroids that aren't rounded to integers
+pub trait ToFloat: DataPoint {
+    /// The same kind of point, with `f64` coordinates
+    type Float: DataPoint;
+
+    /// Converts the point to its `f64` counterpart
+    fn to_float(&self) -> Self::Float;
+
+    /// Converts a point back from its `f64` counterpart, rounding each coordinate to the nearest
+    /// value of the type. Values out of the type's range are clamped to it.
+    fn from_float(float: &Self::Float) -> Self;
+}
+
+/// A clustering of `points` around a `centroid`.
+///
+/// With the `serde` feature, clusters can be serialized and deserialized whenever `P` can, so a
+/// fitted clustering can be saved and loaded again later for `predict`.
+#[cfg(any(feature = "std", feature = "alloc"))]
 #[derive(Debug, Clone, PartialOrd, PartialEq)]
-pub struct Cluster<P: DataPoint> {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Cluster<P> {
     pub centroid: P,
     pub points: Vec<P>
 }
 
-impl<P: DataPoint> Cluster<P> {
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<P> Cluster<P> {
+    /// The number of points in the cluster
+    pub fn size(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Whether the cluster has no points
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Borrows the centroid of each of the clusters, without cloning them
+    pub fn centroids_ref(cs: &[Self]) -> Vec<&P> {
+        cs.iter().map(|c| &c.centroid).collect()
+    }
+
+    /// Throws away the points, keeping only the centroid
+    pub fn into_centroids(self) -> P {
+        self.centroid
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<P: PartialEq> Cluster<P> {
+    /// Whether `p` is one of the cluster's points. This checks each point in turn, so it takes
+    /// time in proportion to the size of the cluster.
+    pub fn contains(&self, p: &P) -> bool {
+        self.points.contains(p)
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<P: PartialOrd> Cluster<P> {
+    /// Sorts the clusters from most points to fewest, breaking ties by putting the smaller
+    /// centroid first, so that the same clustering always comes out in the same order however its
+    /// clusters were found
+    pub fn sort_by_size(cs: &mut [Self]) {
+        cs.sort_by(Self::size_order);
+    }
+
+    fn size_order(a: &Self, b: &Self) -> Ordering {
+        b.points.len().cmp(&a.points.len())
+            .then_with(|| a.centroid.partial_cmp(&b.centroid).unwrap_or(Ordering::Equal))
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<P: Clone> Cluster<P> {
     pub fn new(centroid: &P) -> Self {
         Self { centroid: centroid.clone(), points: vec![] }
     }
 
+    pub fn centroids(cs: &[Self]) -> Vec<P> {
+        cs.iter().map(|c| c.centroid.clone()).collect()
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<P: DataPoint> Cluster<P> {
     pub fn from_intermediate(intermediate: &IntermediateCluster<P>, points: &[P]) -> Self {
         Self {
             centroid: intermediate.centroid.clone(),
@@ -33,300 +189,1220 @@ impl<P: DataPoint> Cluster<P> {
         }
     }
 
+    /// Moves the centroid to the mean of the cluster's points, or leaves it where it is if there
+    /// are none
     pub fn recalculate_centroid(&mut self) {
-        self.centroid = P::mean(&self.points);
+        // Not every `DataPoint` finds no mean of nothing, so don't ask
+        if self.points.is_empty() {
+            return;
+        }
+
+        if let Some(mean) = P::mean(&self.points) {
+            self.centroid = mean;
+        }
     }
 
-    pub fn centroids(cs: &[Self]) -> Vec<P> {
-        cs.iter().map(|c| c.centroid.clone()).collect()
+    /// Moves the centroid to the per-coordinate median of the cluster's points, or leaves it where
+    /// it is if there are none
+    pub fn recalculate_median(&mut self) where P: Median {
+        if !self.points.is_empty() {
+            self.centroid = P::median(&self.points);
+        }
+    }
+
+    /// The sum of squared distances from each point in the cluster to its centroid
+    pub fn inertia(&self) -> f64 {
+        self.points.iter().map(|p| self.centroid.dist_sq(p)).sum()
+    }
+
+    /// The distance from each point in the cluster to its centroid, in the same order as `points`
+    pub fn point_distances(&self) -> Vec<f64> {
+        self.points.iter().map(|p| self.centroid.dist(p)).collect()
+    }
+
+    /// The points that are more than `threshold` away from the centroid
+    pub fn outliers(&self, threshold: f64) -> Vec<&P> {
+        self.points.iter().filter(|p| self.centroid.dist(p) > threshold).collect()
+    }
+
+    /// The points that are farther from the centroid than `percentile` percent of the cluster's
+    /// points are, by the nearest-rank method. `percentile` is clamped to `0.0..=100.0`, so 100
+    /// never finds any outliers, while 0 finds every point but the nearest.
+    pub fn outliers_by_percentile(&self, percentile: f64) -> Vec<&P> {
+        let mut distances = self.point_distances();
+        if distances.is_empty() {
+            return vec![];
+        }
+
+        distances.sort_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * distances.len() as f64).ceil() as usize;
+        self.outliers(distances[rank.max(1) - 1])
+    }
+
+    /// The `n` points closest to the centroid, nearest first, or all of them if there are fewer.
+    /// Points the same distance away stay in the order they're in in `points`.
+    pub fn nearest_points(&self, n: usize) -> Vec<&P> {
+        self.points_by_distance(n, |a, b| a.partial_cmp(b))
+    }
+
+    /// The `n` points farthest from the centroid, farthest first, or all of them if there are
+    /// fewer. Points the same distance away stay in the order they're in in `points`.
+    pub fn farthest_points(&self, n: usize) -> Vec<&P> {
+        self.points_by_distance(n, |a, b| b.partial_cmp(a))
+    }
+
+    fn points_by_distance(&self, n: usize, order: fn(&f64, &f64) -> Option<Ordering>) -> Vec<&P> {
+        let mut points: Vec<(f64, &P)> = self.points.iter().map(|p| (self.centroid.dist_sq(p), p)).collect();
+        points.sort_by(|a, b| order(&a.0, &b.0).unwrap_or(Ordering::Equal));
+        points.into_iter().take(n).map(|(_, p)| p).collect()
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<P: WeightedMean> Cluster<P> {
+    /// Adds `p` to the cluster and moves the centroid to the mean of the points including it.
+    ///
+    /// Rather than averaging every point again, the new centroid is found from the old one and the
+    /// number of points, so this takes constant time. That assumes the centroid is the mean of the
+    /// points to begin with, as after `recalculate_centroid`. An empty cluster's centroid simply
+    /// becomes `p`.
+    ///
+    /// Integer coordinates are rounded at every update, so over many updates the centroid can
+    /// drift from what `recalculate_centroid` would give.
+    pub fn add_point(&mut self, p: P) {
+        let n = self.points.len() as f64;
+        self.centroid = P::weighted_mean(&[self.centroid.clone(), p.clone()], &[n, 1.0]);
+        self.points.push(p);
+    }
+
+    /// Removes the first point `==` to `p` from the cluster and moves the centroid to the mean of
+    /// the points left, the same way `add_point` does. Returns whether there was such a point.
+    ///
+    /// Removing the last point leaves the centroid where it is.
+    pub fn remove_point(&mut self, p: &P) -> bool {
+        let i = match self.points.iter().position(|q| q == p) {
+            Some(i) => i,
+            None => return false
+        };
+
+        let n = self.points.len() as f64;
+        let removed = self.points.remove(i);
+        if !self.points.is_empty() {
+            self.centroid = P::weighted_mean(&[self.centroid.clone(), removed], &[n, -1.0]);
+        }
+        true
+    }
+}
+
+/// Shows the centroid and the number of points, like `Cluster(centroid=(3.0, 3.0), n=18)`.
+///
+/// Tuples and arrays have no `Display` of their own, so the centroid is shown with `Debug`.
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<P: Debug> Display for Cluster<P> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Cluster(centroid={:?}, n={})", self.centroid, self.points.len())
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<P> IntoIterator for Cluster<P> {
+    type Item = P;
+    type IntoIter = <Vec<P> as IntoIterator>::IntoIter;
+
+    /// Iterates over the points, throwing away the centroid
+    fn into_iter(self) -> Self::IntoIter {
+        self.points.into_iter()
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<'a, P> IntoIterator for &'a Cluster<P> {
+    type Item = &'a P;
+    type IntoIter = core::slice::Iter<'a, P>;
+
+    /// Iterates over references to the points
+    fn into_iter(self) -> Self::IntoIter {
+        self.points.iter()
+    }
+}
+
+/// A `Cluster` that keeps its points in a fixed-capacity `heapless::Vec` instead of on the heap,
+/// for when there's no allocator. It can hold up to `N` points.
+#[cfg(feature = "heapless")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedCluster<P, const N: usize> {
+    pub centroid: P,
+    pub points: heapless::Vec<P, N>
+}
+
+#[cfg(feature = "heapless")]
+impl<P, const N: usize> FixedCluster<P, N> {
+    /// The number of points in the cluster
+    pub fn size(&self) -> usize {
+        self.points.len()
+    }
+}
+
+/// The outcome of a k-means run, along with some information about how it went
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct KMeansResult<P: DataPoint> {
+    pub clusters: Vec<Cluster<P>>,
+    /// The index into `clusters` that each input point was assigned to, in input order
+    pub labels: Vec<usize>,
+    /// The number of recenter/reassign rounds that were run
+    pub iterations: usize,
+    /// Whether the clustering stopped changing before the iteration cap was reached. It's also
+    /// `false` if the clustering was going around in a cycle of states, in which case `clusters`
+    /// is the best of them.
+    pub converged: bool,
+    /// The `total_inertia` of the `clusters`, worked out before any of their points were let go,
+    /// as with `KMeans::with_retained_points(false)`
+    pub inertia: f64,
+    /// The `total_inertia` at the end of each round, one per iteration, as recorded by
+    /// `kmeans_detailed` when asked to. Everything else leaves this empty rather than pay to work
+    /// it out.
+    pub inertia_history: Vec<f64>
+}
+
+/// How the starting centroids are chosen
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum InitMethod {
+    /// [k-means++]: each centroid is picked at random, weighted by its squared distance from the
+    /// centroids picked so far. Slower to start, but usually converges faster and to a better
+    /// clustering.
+    ///
+    /// [k-means++]: https://en.wikipedia.org/wiki/K-means%2B%2B
+    #[default]
+    KMeansPlusPlus,
+    /// Forgy's method: `k` distinct points are picked uniformly at random
+    Forgy,
+    /// [k-means||], which samples many candidates at once over a few rounds instead of one at a time
+    /// over `k` rounds, then narrows them down to `k` with k-means++.
+    ///
+    /// `l` is the oversampling factor, the number of candidates expected to be picked in each of the
+    /// `rounds`. The paper suggests an `l` between `0.5 * k` and `2 * k` and around five rounds;
+    /// more candidates give a better start at the cost of a slower one.
+    ///
+    /// [k-means||]: https://arxiv.org/abs/1203.6402
+    KMeansParallel { l: f64, rounds: usize },
+    /// Greedy k-means++, as in scikit-learn: for each centroid after the first, `2 + ln k`
+    /// candidates are drawn the way k-means++ draws one, and whichever leaves the points closest to
+    /// their nearest centroids overall is kept. That takes a few times as long as k-means++, and
+    /// avoids most of its unlucky picks. With fewer points than `k`, every point is picked.
+    GreedyKMeansPlusPlus
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<P: DataPoint> KMeansResult<P> {
+    /// How many points were assigned to each of the `clusters`, counted from the `labels`, so
+    /// it's right even if the clusters' points weren't kept
+    pub fn sizes(&self) -> Vec<usize> {
+        let mut sizes = vec![0; self.clusters.len()];
+        for label in self.labels.iter() {
+            sizes[*label] += 1;
+        }
+        sizes
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<P: DataPoint + PartialOrd> KMeansResult<P> {
+    /// Sorts the clusters as in `Cluster::sort_by_size`, updating `labels` to match
+    pub fn sort_by_size(&mut self) {
+        let mut order: Vec<usize> = (0..self.clusters.len()).collect();
+        order.sort_by(|a, b| Cluster::size_order(&self.clusters[*a], &self.clusters[*b]));
+
+        let mut new_label = vec![0; order.len()];
+        for (new, old) in order.iter().enumerate() {
+            new_label[*old] = new;
+        }
+        for label in self.labels.iter_mut() {
+            *label = new_label[*label];
+        }
+
+        let mut clusters: Vec<Option<Cluster<P>>> = self.clusters.drain(..).map(Some).collect();
+        self.clusters = order.iter().map(|old| clusters[*old].take().unwrap()).collect();
     }
 }
 
+/// The ways clustering can fail up front, before any work is done
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KMeansError {
+    /// There were no points to cluster
+    NoData,
+    /// No starting centroids were given
+    NoCentroids,
+    /// More clusters were asked for than there are points to seed them with
+    TooFewPoints { k: usize, n: usize },
+    /// There aren't enough points to give every one of `k` clusters at least `min_size` of them
+    MinSizeTooLarge { k: usize, min_size: usize, n: usize }
+}
+
+impl Display for KMeansError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KMeansError::NoData => write!(f, "no points to cluster"),
+            KMeansError::NoCentroids => write!(f, "no starting centroids"),
+            KMeansError::TooFewPoints { k, n } => write!(f, "cannot make {} clusters from {} points", k, n),
+            KMeansError::MinSizeTooLarge { k, min_size, n } => {
+                write!(f, "cannot make {} clusters of at least {} points from {} points", k, min_size, n)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KMeansError {}
+
+/// A cluster that refers to its points by their indices in some slice, rather than holding
+/// copies of them
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Debug, Clone, PartialEq)]
 pub struct IntermediateCluster<P: DataPoint> {
     pub centroid: P,
     pub point_indices: Vec<usize>
 }
 
+#[cfg(any(feature = "std", feature = "alloc"))]
 impl<P: DataPoint> IntermediateCluster<P> {
     pub fn new(centroid: &P) -> Self {
         Self { centroid: centroid.clone(), point_indices: vec![] }
     }
+
+    /// Moves the centroid to the mean of the cluster's members in `points`, returning where it was
+    pub fn recalculate_centroid(&mut self, points: &[P]) -> P {
+        self.recenter(points, P::mean_refs)
+    }
+
+    /// Moves the centroid to the `center` of the cluster's members in `points`, returning where it
+    /// was. If `center` gives `None`, the centroid stays where it is.
+    pub fn recenter(&mut self, points: &[P], center: fn(&[&P]) -> Option<P>) -> P {
+        let members: Vec<&P> = self.point_indices.iter().map(|i| &points[*i]).collect();
+        match center(&members) {
+            Some(centroid) => core::mem::replace(&mut self.centroid, centroid),
+            None => self.centroid.clone()
+        }
+    }
+
+    /// The sum of squared distances from each of the cluster's members in `points` to its centroid
+    pub fn inertia(&self, points: &[P]) -> f64 {
+        self.point_indices.iter().map(|i| self.centroid.dist_sq(&points[*i])).sum()
+    }
 }
 
 /*** IMPLS FOR COMMON POINT REPRESENTATIONS ***/
 
-macro_rules! impl_float_2d_data_point {
-    ($T:ty) => {
-        impl DataPoint for ($T, $T) {
-            fn dist(&self, other: &Self) -> f64 {
-                f64::sqrt(
-                    (self.0 - other.0).powi(2) as f64 +
-                    (self.1 - other.1).powi(2) as f64
-                )
-            }
+/// A number that can be used as a coordinate of the built-in point types.
+///
+/// This is implemented for all of the primitive integer and floating-point types.
+pub trait Scalar: Copy + PartialEq + ToPrimitive + FromPrimitive {
+    /// The type coordinates are added up in to find their mean. Integers of up to 64 bits are
+    /// added up as 128-bit integers, so the sum is exact and can't overflow; everything else is
+    /// added up as a `CompensatedSum` of `f64`s.
+    type Sum: Copy + Default + Add<Output = Self::Sum>;
+
+    /// Converts the coordinate so it can be added to a `Sum`
+    fn into_sum(self) -> Self::Sum;
+
+    /// Divides the `sum` of `n` coordinates back down to a coordinate, truncating toward zero for
+    /// integer types
+    fn mean_of(sum: Self::Sum, n: usize) -> Self;
+
+    /// Converts a computed `f64` back to a coordinate, truncating toward zero for integer types.
+    /// Values too big or too small for the type are clamped to its largest or smallest value, and
+    /// NaN becomes zero.
+    fn saturating_from_f64(x: f64) -> Self;
+}
 
-            fn mean(ps: &[Self]) -> Self {
-                let sum = ps.iter()
-                    .fold((0.0, 0.0), |acc, next| (acc.0 + next.0, acc.1 + next.1));
+macro_rules! impl_scalar {
+    ($sum:ty; $($t:ty),+) => {
+        $(
+            impl Scalar for $t {
+                type Sum = $sum;
 
-                (sum.0 / ps.len() as $T, sum.1 / ps.len() as $T)
+                fn into_sum(self) -> $sum {
+                    self as $sum
+                }
+
+                fn mean_of(sum: $sum, n: usize) -> Self {
+                    // The mean is never outside the range of the coordinates that went into it,
+                    // so this can't wrap
+                    (sum / n as $sum) as $t
+                }
+
+                fn saturating_from_f64(x: f64) -> Self {
+                    x as $t
+                }
             }
-        }
+        )+
     };
 }
 
-macro_rules! impl_unsigned_2d_data_point {
-    ($T:ty) => {
-        impl DataPoint for ($T, $T) {
-            fn dist(&self, other: &Self) -> f64 {
-                fn abs_sub(a: $T, b: $T) -> $T {
-                    if a > b {
-                        a - b
-                    } else {
-                        b - a
-                    }
+macro_rules! impl_float_scalar {
+    ($($t:ty),+) => {
+        $(
+            impl Scalar for $t {
+                type Sum = CompensatedSum;
+
+                fn into_sum(self) -> CompensatedSum {
+                    CompensatedSum::from(self as f64)
                 }
 
-                f64::sqrt(
-                    abs_sub(self.0, other.0).pow(2) as f64 +
-                    abs_sub(self.1, other.1).pow(2) as f64
-                )
+                fn mean_of(sum: CompensatedSum, n: usize) -> Self {
+                    (sum.total() / n as f64) as $t
+                }
+
+                fn saturating_from_f64(x: f64) -> Self {
+                    x as $t
+                }
             }
+        )+
+    };
+}
 
-            fn mean(ps: &[Self]) -> Self {
-                let sum = ps.iter()
-                    .fold((0, 0), |acc, next| (acc.0 + next.0 as usize, acc.1 + next.0 as usize));
+impl_scalar!(i128; i8, i16, i32, i64, isize);
+impl_scalar!(u128; u8, u16, u32, u64, usize);
+impl_float_scalar!(f32, f64, i128, u128);
 
-                (
-                    <$T>::try_from(sum.0 / ps.len()).unwrap(),
-                    <$T>::try_from(sum.1 / ps.len()).unwrap()
-                )
-            }
+/// A running `f64` sum that keeps track of the rounding error lost by each addition, using
+/// [Neumaier's] variant of Kahan summation.
+///
+/// Adding up a long list of floats one at a time lets the error build up with every addition, and
+/// the result depends on the order they're added in. Carrying the lost low-order bits alongside the
+/// sum keeps it accurate to about the last bit however many values go into it, which is how the
+/// means of float points are found.
+///
+/// [Neumaier's]: https://en.wikipedia.org/wiki/Kahan_summation_algorithm#Further_enhancements
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CompensatedSum {
+    sum: f64,
+    compensation: f64
+}
+
+impl CompensatedSum {
+    /// The sum of everything added so far, with the lost rounding error added back in
+    pub fn total(&self) -> f64 {
+        self.sum + self.compensation
+    }
+}
+
+impl From<f64> for CompensatedSum {
+    fn from(x: f64) -> Self {
+        CompensatedSum { sum: x, compensation: 0.0 }
+    }
+}
+
+impl Add<f64> for CompensatedSum {
+    type Output = Self;
+
+    fn add(self, x: f64) -> Self {
+        let sum = self.sum + x;
+        // Whichever of the two is smaller is the one whose low bits got rounded off
+        let lost = if self.sum.abs() >= x.abs() {
+            (self.sum - sum) + x
+        } else {
+            (x - sum) + self.sum
+        };
+        CompensatedSum { sum, compensation: self.compensation + lost }
+    }
+}
+
+impl Add for CompensatedSum {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        let mut total = self + other.sum;
+        total.compensation += other.compensation;
+        total
+    }
+}
+
+/// Converts a coordinate to `f64` for doing arithmetic on it
+fn to_f64<T: Scalar>(x: T) -> f64 {
+    x.to_f64().expect("coordinate cannot be represented as an f64")
+}
+
+/// Converts a computed coordinate back from `f64`, as in `Scalar::saturating_from_f64`
+fn from_f64<T: Scalar>(x: f64) -> T {
+    T::saturating_from_f64(x)
+}
+
+/// The element-wise mean of some arrays, or the origin if there are none
+fn array_mean<T: Scalar, const N: usize, I: Iterator<Item = [T; N]>>(ps: I) -> [T; N] {
+    let mut sum = [T::Sum::default(); N];
+    let mut count = 0;
+    for p in ps {
+        for (acc, next) in sum.iter_mut().zip(p.iter()) {
+            *acc = *acc + next.into_sum();
         }
-    };
+        count += 1;
+    }
+
+    let mut mean = [from_f64(0.0); N];
+    if count != 0 {
+        for (m, acc) in mean.iter_mut().zip(sum.iter()) {
+            *m = T::mean_of(*acc, count);
+        }
+    }
+    mean
 }
 
-macro_rules! impl_signed_2d_data_point {
-    ($T:ty) => {
-        impl DataPoint for ($T, $T) {
-            fn dist(&self, other: &Self) -> f64 {
-                f64::sqrt(
-                    (self.0 as isize - other.0 as isize).pow(2) as f64 +
-                    (self.1 as isize - other.1 as isize).pow(2) as f64
-                )
-            }
+/// `array_mean` for the code `#[derive(DataPoint)]` generates, which can't see private items
+#[doc(hidden)]
+pub fn __derived_mean<const N: usize, I: Iterator<Item = [f64; N]>>(ps: I) -> [f64; N] {
+    array_mean(ps)
+}
 
-            fn mean(ps: &[Self]) -> Self {
-                let sum = ps.iter()
-                    .fold((0, 0), |acc, next| (acc.0 + next.0 as isize, acc.1 + next.0 as isize));
+/// The element-wise weighted mean of some arrays, or the origin if the weights sum to zero
+fn array_weighted_mean<T: Scalar, const N: usize, I: Iterator<Item = ([T; N], f64)>>(ps: I) -> [T; N] {
+    let mut sum = [CompensatedSum::default(); N];
+    let mut total = CompensatedSum::default();
+    for (p, w) in ps {
+        for (acc, next) in sum.iter_mut().zip(p.iter()) {
+            *acc = *acc + to_f64(*next) * w;
+        }
+        total = total + w;
+    }
 
-                (
-                    <$T>::try_from(sum.0 / ps.len() as isize).unwrap(),
-                    <$T>::try_from(sum.1 / ps.len() as isize).unwrap()
-                )
-            }
+    let total = total.total();
+    let mut mean = [from_f64(0.0); N];
+    if total != 0.0 {
+        for (m, acc) in mean.iter_mut().zip(sum.iter()) {
+            *m = from_f64(acc.total() / total);
         }
-    };
+    }
+    mean
 }
 
-macro_rules! impl_float_3d_data_point {
-    ($T:ty) => {
-        impl DataPoint for ($T, $T, $T) {
-            fn dist(&self, other: &Self) -> f64 {
-                f64::sqrt(
-                    (self.0 - other.0).powi(2) as f64 +
-                    (self.1 - other.1).powi(2) as f64 +
-                    (self.2 - other.2).powi(2) as f64
-                )
-            }
+/// The median of some values, averaging the middle two if there's an even number of them, or zero if
+/// there are none
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn median_of(values: &mut [f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
 
-            fn mean(ps: &[Self]) -> Self {
-                let sum = ps.iter()
-                    .fold((0.0, 0.0, 0.0), |acc, next| (acc.0 + next.0, acc.1 + next.1, acc.2 + next.2));
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(core::cmp::Ordering::Equal));
+    let mid = values.len() / 2;
+    if values.len() % 2 == 1 {
+        values[mid]
+    } else {
+        (values[mid - 1] + values[mid]) / 2.0
+    }
+}
 
-                (sum.0 / ps.len() as $T, sum.1 / ps.len() as $T, sum.2 / ps.len() as $T)
-            }
+/// The element-wise median of some arrays, or the origin if there are none
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn array_median<T: Scalar, const N: usize>(ps: &[[T; N]]) -> [T; N] {
+    let mut median = [from_f64(0.0); N];
+    let mut values = Vec::with_capacity(ps.len());
+    for (i, m) in median.iter_mut().enumerate() {
+        values.clear();
+        values.extend(ps.iter().map(|p| to_f64(p[i])));
+        *m = from_f64(median_of(&mut values));
+    }
+    median
+}
+
+/// Compares two lists of coordinates in order, as in `DataPoint::tie_order`. NaN is equal to
+/// everything.
+fn lexicographic_order<I: Iterator<Item = f64>>(a: I, b: I) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+/// Points with a fixed number of dimensions, of any primitive number type
+impl<T: Scalar, const N: usize> DataPoint for [T; N] {
+    fn dist(&self, other: &Self) -> f64 {
+        f64::sqrt(self.dist_sq(other))
+    }
+
+    fn dist_sq(&self, other: &Self) -> f64 {
+        self.iter().zip(other.iter()).map(|(a, b)| (to_f64(*a) - to_f64(*b)).powi(2)).sum()
+    }
+
+    /// The element-wise mean. Integer coordinates are truncated toward zero.
+    fn mean(ps: &[Self]) -> Option<Self> {
+        if ps.is_empty() {
+            return None;
         }
-    };
+
+        Some(array_mean(ps.iter().cloned()))
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn mean_refs(ps: &[&Self]) -> Option<Self> {
+        if ps.is_empty() {
+            return None;
+        }
+
+        Some(array_mean(ps.iter().map(|p| **p)))
+    }
+
+    fn tie_order(&self, other: &Self) -> Ordering {
+        lexicographic_order(self.iter().cloned().map(to_f64), other.iter().cloned().map(to_f64))
+    }
+}
+
+impl<T: Scalar, const N: usize> WeightedMean for [T; N] {
+    /// The element-wise weighted mean, or the origin if the weights sum to zero. Integer
+    /// coordinates are truncated toward zero, and negative weights can push the mean outside the
+    /// range of the coordinate type, in which case it's clamped rather than panicking.
+    fn weighted_mean(ps: &[Self], weights: &[f64]) -> Self {
+        array_weighted_mean(ps.iter().cloned().zip(weights.iter().cloned()))
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn weighted_mean_refs(ps: &[&Self], weights: &[f64]) -> Self {
+        array_weighted_mean(ps.iter().map(|p| **p).zip(weights.iter().cloned()))
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl<T: Scalar, const N: usize> Median for [T; N] {
+    fn median(ps: &[Self]) -> Self {
+        array_median(ps)
+    }
+}
+
+impl<T: Scalar, const N: usize> ToFloat for [T; N] {
+    type Float = [f64; N];
+
+    fn to_float(&self) -> Self::Float {
+        let mut float = [0.0; N];
+        for (f, x) in float.iter_mut().zip(self.iter()) {
+            *f = to_f64(*x);
+        }
+        float
+    }
+
+    fn from_float(float: &Self::Float) -> Self {
+        core::array::from_fn(|i| from_f64(float[i].round()))
+    }
 }
 
-macro_rules! impl_unsigned_3d_data_point {
-    ($T:ty) => {
-        impl DataPoint for ($T, $T, $T) {
+impl<T: Scalar, const N: usize> Coordinates for [T; N] {
+    fn dimensions(&self) -> usize {
+        N
+    }
+
+    fn coordinate(&self, i: usize) -> f64 {
+        to_f64(self[i])
+    }
+}
+
+impl<T: Scalar, const N: usize> Normalize for [T; N] {
+    /// Integer coordinates are truncated toward zero, so this is only useful for floating point
+    /// ones
+    fn normalized(&self) -> Self {
+        let norm = f64::sqrt(self.iter().map(|x| to_f64(*x).powi(2)).sum());
+        if norm == 0.0 {
+            return *self;
+        }
+
+        let mut normalized = *self;
+        for x in normalized.iter_mut() {
+            *x = from_f64(to_f64(*x) / norm);
+        }
+        normalized
+    }
+}
+
+macro_rules! tuple_element {
+    ($i:tt) => { T };
+}
+
+macro_rules! float_element {
+    ($i:tt) => { f64 };
+}
+
+/// Implements the point traits for a tuple by converting it to an array
+macro_rules! impl_tuple_data_point {
+    ($($i:tt),+) => {
+        impl<T: Scalar> DataPoint for ($(tuple_element!($i),)+) {
             fn dist(&self, other: &Self) -> f64 {
-                fn abs_sub(a: $T, b: $T) -> $T {
-                    if a > b {
-                        a - b
-                    } else {
-                        b - a
-                    }
+                [$(self.$i),+].dist(&[$(other.$i),+])
+            }
+
+            fn dist_sq(&self, other: &Self) -> f64 {
+                [$(self.$i),+].dist_sq(&[$(other.$i),+])
+            }
+
+            fn mean(ps: &[Self]) -> Option<Self> {
+                if ps.is_empty() {
+                    return None;
                 }
 
-                f64::sqrt(
-                    abs_sub(self.0, other.0).pow(2) as f64 +
-                    abs_sub(self.1, other.1).pow(2) as f64 +
-                    abs_sub(self.2, other.2).pow(2) as f64
-                )
+                let mean = array_mean(ps.iter().map(|p| [$(p.$i),+]));
+                Some(($(mean[$i],)+))
             }
 
-            fn mean(ps: &[Self]) -> Self {
-                let sum = ps.iter()
-                    .fold((0, 0, 0), |acc, next| (acc.0 + next.0 as usize, acc.1 + next.0 as usize, acc.2 + next.2 as usize));
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            fn mean_refs(ps: &[&Self]) -> Option<Self> {
+                if ps.is_empty() {
+                    return None;
+                }
 
-                (
-                    <$T>::try_from(sum.0 / ps.len()).unwrap(),
-                    <$T>::try_from(sum.1 / ps.len()).unwrap(),
-                    <$T>::try_from(sum.2 / ps.len()).unwrap()
-                )
+                let mean = array_mean(ps.iter().map(|p| [$(p.$i),+]));
+                Some(($(mean[$i],)+))
+            }
+
+            fn tie_order(&self, other: &Self) -> Ordering {
+                [$(self.$i),+].tie_order(&[$(other.$i),+])
             }
         }
-    };
-}
 
-macro_rules! impl_signed_3d_data_point {
-    ($T:ty) => {
-        impl DataPoint for ($T, $T, $T) {
-            fn dist(&self, other: &Self) -> f64 {
-                f64::sqrt(
-                    (self.0 as isize - other.0 as isize).pow(2) as f64 +
-                    (self.1 as isize - other.1 as isize).pow(2) as f64 +
-                    (self.2 as isize - other.2 as isize).pow(2) as f64
-                )
+        impl<T: Scalar> WeightedMean for ($(tuple_element!($i),)+) {
+            fn weighted_mean(ps: &[Self], weights: &[f64]) -> Self {
+                let mean = array_weighted_mean(ps.iter().map(|p| [$(p.$i),+]).zip(weights.iter().cloned()));
+                ($(mean[$i],)+)
+            }
+
+            #[cfg(any(feature = "std", feature = "alloc"))]
+            fn weighted_mean_refs(ps: &[&Self], weights: &[f64]) -> Self {
+                let mean = array_weighted_mean(ps.iter().map(|p| [$(p.$i),+]).zip(weights.iter().cloned()));
+                ($(mean[$i],)+)
+            }
+        }
+
+        #[cfg(any(feature = "std", feature = "alloc"))]
+        impl<T: Scalar> Median for ($(tuple_element!($i),)+) {
+            fn median(ps: &[Self]) -> Self {
+                let arrays: Vec<[T; [$($i),+].len()]> = ps.iter().map(|p| [$(p.$i),+]).collect();
+                let median = array_median(&arrays);
+                ($(median[$i],)+)
+            }
+        }
+
+        impl<T: Scalar> Normalize for ($(tuple_element!($i),)+) {
+            fn normalized(&self) -> Self {
+                let normalized = [$(self.$i),+].normalized();
+                ($(normalized[$i],)+)
             }
+        }
+
+        impl<T: Scalar> ToFloat for ($(tuple_element!($i),)+) {
+            type Float = ($(float_element!($i),)+);
 
-            fn mean(ps: &[Self]) -> Self {
-                let sum = ps.iter()
-                    .fold((0, 0, 0), |acc, next| (acc.0 + next.0 as isize, acc.1 + next.0 as isize, acc.2 + next.2 as isize));
+            fn to_float(&self) -> Self::Float {
+                ($(to_f64(self.$i),)+)
+            }
 
-                (
-                    <$T>::try_from(sum.0 / ps.len() as isize).unwrap(),
-                    <$T>::try_from(sum.1 / ps.len() as isize).unwrap(),
-                    <$T>::try_from(sum.2 / ps.len() as isize).unwrap()
-                )
+            fn from_float(float: &Self::Float) -> Self {
+                ($(from_f64(float.$i.round()),)+)
+            }
+        }
+
+        impl<T: Scalar> Coordinates for ($(tuple_element!($i),)+) {
+            fn dimensions(&self) -> usize {
+                [$(self.$i),+].len()
+            }
+
+            fn coordinate(&self, i: usize) -> f64 {
+                [$(self.$i),+].coordinate(i)
             }
         }
     };
 }
 
-macro_rules! impl_float_4d_data_point {
-    ($T:ty) => {
-        impl DataPoint for ($T, $T, $T, $T) {
-            fn dist(&self, other: &Self) -> f64 {
-                f64::sqrt(
-                    (self.0 - other.0).powi(2) as f64 +
-                    (self.1 - other.1).powi(2) as f64 +
-                    (self.2 - other.2).powi(2) as f64 +
-                    (self.3 - other.3).powi(2) as f64
-                )
-            }
+impl_tuple_data_point!(0, 1);
+impl_tuple_data_point!(0, 1, 2);
+impl_tuple_data_point!(0, 1, 2, 3);
+
+/// A color, by its red, green and blue components, for reducing an image to a palette with
+/// `quantize`.
+///
+/// Colors are compared by the straight-line distance between their components, and averaged
+/// component by component, truncating toward zero, the same as the `(u8, u8, u8)` they're made
+/// of. That's not how far apart colors look to the eye, but it's close enough for picking a
+/// palette, and a mean of `u8`s is always a valid `u8`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl DataPoint for Rgb {
+    fn dist(&self, other: &Self) -> f64 {
+        (self.0, self.1, self.2).dist(&(other.0, other.1, other.2))
+    }
+
+    fn dist_sq(&self, other: &Self) -> f64 {
+        (self.0, self.1, self.2).dist_sq(&(other.0, other.1, other.2))
+    }
+
+    fn mean(ps: &[Self]) -> Option<Self> {
+        if ps.is_empty() {
+            return None;
+        }
+
+        let mean = array_mean(ps.iter().map(|p| [p.0, p.1, p.2]));
+        Some(Rgb(mean[0], mean[1], mean[2]))
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn mean_refs(ps: &[&Self]) -> Option<Self> {
+        if ps.is_empty() {
+            return None;
+        }
+
+        let mean = array_mean(ps.iter().map(|p| [p.0, p.1, p.2]));
+        Some(Rgb(mean[0], mean[1], mean[2]))
+    }
+
+    fn tie_order(&self, other: &Self) -> Ordering {
+        (self.0, self.1, self.2).cmp(&(other.0, other.1, other.2))
+    }
+}
+
+impl WeightedMean for Rgb {
+    fn weighted_mean(ps: &[Self], weights: &[f64]) -> Self {
+        let mean = array_weighted_mean(ps.iter().map(|p| [p.0, p.1, p.2]).zip(weights.iter().cloned()));
+        Rgb(mean[0], mean[1], mean[2])
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn weighted_mean_refs(ps: &[&Self], weights: &[f64]) -> Self {
+        let mean = array_weighted_mean(ps.iter().map(|p| [p.0, p.1, p.2]).zip(weights.iter().cloned()));
+        Rgb(mean[0], mean[1], mean[2])
+    }
+}
+
+/// Points on a line, for clustering plain numbers. Everything but the distance is worked out as
+/// for `[f64; 1]`.
+impl DataPoint for f64 {
+    fn dist(&self, other: &Self) -> f64 {
+        (self - other).abs()
+    }
+
+    fn dist_sq(&self, other: &Self) -> f64 {
+        (self - other).powi(2)
+    }
+
+    fn mean(ps: &[Self]) -> Option<Self> {
+        if ps.is_empty() {
+            return None;
+        }
+
+        let [mean] = array_mean(ps.iter().map(|p| [*p]));
+        Some(mean)
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn mean_refs(ps: &[&Self]) -> Option<Self> {
+        if ps.is_empty() {
+            return None;
+        }
+
+        let [mean] = array_mean(ps.iter().map(|p| [**p]));
+        Some(mean)
+    }
+
+    fn tie_order(&self, other: &Self) -> Ordering {
+        [*self].tie_order(&[*other])
+    }
+}
+
+impl WeightedMean for f64 {
+    fn weighted_mean(ps: &[Self], weights: &[f64]) -> Self {
+        let [mean] = array_weighted_mean(ps.iter().map(|p| [*p]).zip(weights.iter().cloned()));
+        mean
+    }
+
+    #[cfg(any(feature = "std", feature = "alloc"))]
+    fn weighted_mean_refs(ps: &[&Self], weights: &[f64]) -> Self {
+        let [mean] = array_weighted_mean(ps.iter().map(|p| [**p]).zip(weights.iter().cloned()));
+        mean
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Median for f64 {
+    fn median(ps: &[Self]) -> Self {
+        median_of(&mut ps.to_vec())
+    }
+}
 
-            fn mean(ps: &[Self]) -> Self {
-                let sum = ps.iter()
-                    .fold((0.0, 0.0, 0.0, 0.0), |acc, next| (acc.0 + next.0, acc.1 + next.1, acc.2 + next.2, acc.3 + next.3));
+impl Normalize for f64 {
+    fn normalized(&self) -> Self {
+        let [normalized] = [*self].normalized();
+        normalized
+    }
+}
+
+impl Coordinates for f64 {
+    fn dimensions(&self) -> usize {
+        1
+    }
+
+    fn coordinate(&self, _: usize) -> f64 {
+        *self
+    }
+}
 
-                (sum.0 / ps.len() as $T, sum.1 / ps.len() as $T, sum.2 / ps.len() as $T, sum.3 / ps.len() as $T)
+/// Bit vectors, such as binary feature hashes, compared by [Hamming distance]: the number of bits
+/// that differ. The mean is a majority vote: each bit is set if it's set in more than half of the
+/// points, so ties come out as 0.
+///
+/// [Hamming distance]: https://en.wikipedia.org/wiki/Hamming_distance
+impl DataPoint for u64 {
+    fn dist(&self, other: &Self) -> f64 {
+        (self ^ other).count_ones() as f64
+    }
+
+    fn mean(ps: &[Self]) -> Option<Self> {
+        if ps.is_empty() {
+            return None;
+        }
+
+        let mut mean = 0;
+        for bit in 0..u64::BITS {
+            let votes = ps.iter().filter(|p| *p >> bit & 1 == 1).count();
+            if votes * 2 > ps.len() {
+                mean |= 1 << bit;
             }
         }
+        Some(mean)
+    }
+
+    fn tie_order(&self, other: &Self) -> Ordering {
+        self.cmp(other)
+    }
+}
+
+/// Points of any number of dimensions. All the points being clustered should have the same length.
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl DataPoint for Vec<f64> {
+    fn dist(&self, other: &Self) -> f64 {
+        f64::sqrt(self.dist_sq(other))
+    }
+
+    fn dist_sq(&self, other: &Self) -> f64 {
+        debug_assert_eq!(self.len(), other.len(), "points have different dimensions");
+        self.iter().zip(other.iter()).map(|(a, b)| (a - b).powi(2)).sum()
+    }
+
+    fn mean(ps: &[Self]) -> Option<Self> {
+        vec_mean(ps.iter(), ps.len())
+    }
+
+    fn mean_refs(ps: &[&Self]) -> Option<Self> {
+        vec_mean(ps.iter().cloned(), ps.len())
+    }
+
+    fn tie_order(&self, other: &Self) -> Ordering {
+        lexicographic_order(self.iter().cloned(), other.iter().cloned())
+    }
+}
+
+/// The element-wise mean of `n` vectors, or `None` if there are none
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn vec_mean<'a, I: Iterator<Item = &'a Vec<f64>>>(mut ps: I, n: usize) -> Option<Vec<f64>> {
+    let first = ps.next()?;
+    let mut sum: Vec<CompensatedSum> = first.iter().cloned().map(CompensatedSum::from).collect();
+    for p in ps {
+        debug_assert_eq!(p.len(), sum.len(), "points have different dimensions");
+        for (acc, next) in sum.iter_mut().zip(p.iter()) {
+            *acc = *acc + *next;
+        }
+    }
+
+    Some(sum.iter().map(|acc| acc.total() / n as f64).collect())
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl WeightedMean for Vec<f64> {
+    fn weighted_mean(ps: &[Self], weights: &[f64]) -> Self {
+        vec_weighted_mean(ps.iter().zip(weights.iter().cloned()))
+    }
+
+    fn weighted_mean_refs(ps: &[&Self], weights: &[f64]) -> Self {
+        vec_weighted_mean(ps.iter().cloned().zip(weights.iter().cloned()))
+    }
+}
+
+/// The element-wise weighted mean of some vectors, the origin if the weights sum to zero, or an
+/// empty vector if there are none
+#[cfg(any(feature = "std", feature = "alloc"))]
+fn vec_weighted_mean<'a, I: Iterator<Item = (&'a Vec<f64>, f64)>>(ps: I) -> Vec<f64> {
+    let mut ps = ps.peekable();
+    let dimensions = match ps.peek() {
+        Some((p, _)) => p.len(),
+        None => return vec![]
     };
+
+    let mut sum = vec![CompensatedSum::default(); dimensions];
+    let mut total = CompensatedSum::default();
+    for (p, w) in ps {
+        debug_assert_eq!(p.len(), dimensions, "points have different dimensions");
+        for (acc, next) in sum.iter_mut().zip(p.iter()) {
+            *acc = *acc + next * w;
+        }
+        total = total + w;
+    }
+
+    let total = total.total();
+    if total == 0.0 {
+        return vec![0.0; dimensions];
+    }
+    sum.iter().map(|acc| acc.total() / total).collect()
 }
 
-macro_rules! impl_unsigned_4d_data_point {
-    ($T:ty) => {
-        impl DataPoint for ($T, $T, $T, $T) {
-            fn dist(&self, other: &Self) -> f64 {
-                fn abs_sub(a: $T, b: $T) -> $T {
-                    if a > b {
-                        a - b
-                    } else {
-                        b - a
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Median for Vec<f64> {
+    fn median(ps: &[Self]) -> Self {
+        let dimensions = match ps.first() {
+            Some(p) => p.len(),
+            None => return vec![]
+        };
+
+        let mut values = Vec::with_capacity(ps.len());
+        (0..dimensions)
+            .map(|i| {
+                values.clear();
+                values.extend(ps.iter().map(|p| p[i]));
+                median_of(&mut values)
+            })
+            .collect()
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Normalize for Vec<f64> {
+    fn normalized(&self) -> Self {
+        let norm = f64::sqrt(self.iter().map(|x| x.powi(2)).sum());
+        if norm == 0.0 {
+            return self.clone();
+        }
+
+        self.iter().map(|x| x / norm).collect()
+    }
+}
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl Coordinates for Vec<f64> {
+    fn dimensions(&self) -> usize {
+        self.len()
+    }
+
+    fn coordinate(&self, i: usize) -> f64 {
+        self[i]
+    }
+}
+
+/// One field of a `MixedPoint`: a number, or a category, such as a color or a country, given by
+/// an id of the caller's choosing
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Field {
+    Numeric(f64),
+    Categorical(u64)
+}
+
+/// A record whose fields mix numbers and categories. All the points being clustered should have
+/// the same kind of field in each position.
+///
+/// Points are compared by the `Gower` metric with every numeric field taken to span a range of
+/// one, so `dist` is only the true Gower distance once the numeric fields have been scaled to
+/// `0.0..=1.0`, with `minmax_scale` say. Otherwise, use `Gower::fit` with `kmeans_with_metric`.
+///
+/// The mean of some points takes the mean of each numeric field and the most common value of each
+/// categorical field, or the smallest id of the most common, as in k-prototypes.
+#[cfg(any(feature = "std", feature = "alloc"))]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MixedPoint(pub Vec<Field>);
+
+#[cfg(any(feature = "std", feature = "alloc"))]
+impl DataPoint for MixedPoint {
+    fn dist(&self, other: &Self) -> f64 {
+        Gower::default().distance(self, other)
+    }
+
+    fn mean(ps: &[Self]) -> Option<Self> {
+        let first = ps.first()?;
+        let fields = first.0.iter().enumerate()
+            .map(|(i, field)| match field {
+                Field::Numeric(_) => {
+                    let values = ps.iter().filter_map(|p| match p.0[i] {
+                        Field::Numeric(x) => Some(x),
+                        Field::Categorical(_) => None
+                    });
+                    let (sum, count) = values.fold((CompensatedSum::default(), 0), |(sum, count), x| (sum + x, count + 1));
+                    Field::Numeric(sum.total() / count as f64)
+                }
+                Field::Categorical(_) => {
+                    let mut categories: Vec<u64> = ps.iter().filter_map(|p| match p.0[i] {
+                        Field::Categorical(c) => Some(c),
+                        Field::Numeric(_) => None
+                    }).collect();
+                    categories.sort_unstable();
+                    // The first of the longest runs of the same id
+                    let mut mode = (categories[0], 0);
+                    for run in categories.chunk_by(|a, b| a == b) {
+                        if run.len() > mode.1 {
+                            mode = (run[0], run.len());
+                        }
                     }
+                    Field::Categorical(mode.0)
                 }
+            })
+            .collect();
+        Some(MixedPoint(fields))
+    }
 
-                f64::sqrt(
-                    abs_sub(self.0, other.0).pow(2) as f64 +
-                    abs_sub(self.1, other.1).pow(2) as f64 +
-                    abs_sub(self.2, other.2).pow(2) as f64 +
-                    abs_sub(self.3, other.3).pow(2) as f64
-                )
-            }
+    fn tie_order(&self, other: &Self) -> Ordering {
+        self.0.partial_cmp(&other.0).unwrap_or(Ordering::Equal)
+    }
+}
 
-            fn mean(ps: &[Self]) -> Self {
-                let sum = ps.iter()
-                    .fold((0, 0, 0, 0), |acc, next| (acc.0 + next.0 as usize, acc.1 + next.0 as usize, acc.2 + next.2 as usize, acc.3 + next.3 as usize));
+/// `nalgebra` vectors of any fixed size, with the `nalgebra` feature.
+///
+/// `SVector` has an inherent `mean` of its own components, so this one has to be called as
+/// `<SVector<f64, N> as DataPoint>::mean`.
+#[cfg(feature = "nalgebra")]
+impl<const N: usize> DataPoint for nalgebra::SVector<f64, N> {
+    fn dist(&self, other: &Self) -> f64 {
+        (self - other).norm()
+    }
 
-                (
-                    <$T>::try_from(sum.0 / ps.len()).unwrap(),
-                    <$T>::try_from(sum.1 / ps.len()).unwrap(),
-                    <$T>::try_from(sum.2 / ps.len()).unwrap(),
-                    <$T>::try_from(sum.3 / ps.len()).unwrap()
-                )
-            }
+    fn dist_sq(&self, other: &Self) -> f64 {
+        (self - other).norm_squared()
+    }
+
+    fn mean(ps: &[Self]) -> Option<Self> {
+        if ps.is_empty() {
+            return None;
         }
-    };
+
+        Some(ps.iter().sum::<Self>() / ps.len() as f64)
+    }
+
+    fn tie_order(&self, other: &Self) -> Ordering {
+        lexicographic_order(self.iter().cloned(), other.iter().cloned())
+    }
 }
 
-macro_rules! impl_signed_4d_data_point {
-    ($T:ty) => {
-        impl DataPoint for ($T, $T, $T, $T) {
-            fn dist(&self, other: &Self) -> f64 {
-                f64::sqrt(
-                    (self.0 as isize - other.0 as isize).pow(2) as f64 +
-                    (self.1 as isize - other.1 as isize).pow(2) as f64 +
-                    (self.2 as isize - other.2 as isize).pow(2) as f64 +
-                    (self.3 as isize - other.3 as isize).pow(2) as f64
-                )
-            }
+/// `nalgebra` points of any fixed size, like `Point2<f64>` and `Point3<f64>`, with the `nalgebra`
+/// feature
+#[cfg(feature = "nalgebra")]
+impl<const N: usize> DataPoint for nalgebra::Point<f64, N> {
+    fn dist(&self, other: &Self) -> f64 {
+        nalgebra::distance(self, other)
+    }
 
-            fn mean(ps: &[Self]) -> Self {
-                let sum = ps.iter()
-                    .fold((0, 0, 0, 0), |acc, next| (acc.0 + next.0 as isize, acc.1 + next.0 as isize, acc.2 + next.2 as isize, acc.3 + next.3 as isize));
+    fn dist_sq(&self, other: &Self) -> f64 {
+        nalgebra::distance_squared(self, other)
+    }
 
-                (
-                    <$T>::try_from(sum.0 / ps.len() as isize).unwrap(),
-                    <$T>::try_from(sum.1 / ps.len() as isize).unwrap(),
-                    <$T>::try_from(sum.2 / ps.len() as isize).unwrap(),
-                    <$T>::try_from(sum.3 / ps.len() as isize).unwrap()
-                )
-            }
+    fn mean(ps: &[Self]) -> Option<Self> {
+        let coords: Vec<nalgebra::SVector<f64, N>> = ps.iter().map(|p| p.coords).collect();
+        <nalgebra::SVector<f64, N> as DataPoint>::mean(&coords).map(Self::from)
+    }
+
+    fn tie_order(&self, other: &Self) -> Ordering {
+        self.coords.tie_order(&other.coords)
+    }
+}
+
+/// Complex numbers, as points in the plane, with the `num-complex` feature. The distance between
+/// two is the modulus of their difference, and their mean is the mean of the real parts and of the
+/// imaginary parts.
+#[cfg(feature = "num-complex")]
+impl DataPoint for num_complex::Complex<f64> {
+    fn dist(&self, other: &Self) -> f64 {
+        (self - other).norm()
+    }
+
+    fn dist_sq(&self, other: &Self) -> f64 {
+        (self - other).norm_sqr()
+    }
+
+    fn mean(ps: &[Self]) -> Option<Self> {
+        if ps.is_empty() {
+            return None;
         }
-    };
+
+        let [re, im] = array_mean(ps.iter().map(|p| [p.re, p.im]));
+        Some(Self::new(re, im))
+    }
+
+    fn tie_order(&self, other: &Self) -> Ordering {
+        lexicographic_order([self.re, self.im].iter().cloned(), [other.re, other.im].iter().cloned())
+    }
 }
 
-impl_float_2d_data_point!(f32);
-impl_float_2d_data_point!(f64);
-impl_unsigned_2d_data_point!(u8);
-impl_unsigned_2d_data_point!(u16);
-impl_unsigned_2d_data_point!(u32);
-impl_unsigned_2d_data_point!(u64);
-impl_unsigned_2d_data_point!(usize);
-impl_signed_2d_data_point!(i8);
-impl_signed_2d_data_point!(i16);
-impl_signed_2d_data_point!(i32);
-impl_signed_2d_data_point!(i64);
-impl_signed_2d_data_point!(isize);
-
-impl_float_3d_data_point!(f32);
-impl_float_3d_data_point!(f64);
-impl_unsigned_3d_data_point!(u8);
-impl_unsigned_3d_data_point!(u16);
-impl_unsigned_3d_data_point!(u32);
-impl_unsigned_3d_data_point!(u64);
-impl_unsigned_3d_data_point!(usize);
-impl_signed_3d_data_point!(i8);
-impl_signed_3d_data_point!(i16);
-impl_signed_3d_data_point!(i32);
-impl_signed_3d_data_point!(i64);
-impl_signed_3d_data_point!(isize);
-
-impl_float_4d_data_point!(f32);
-impl_float_4d_data_point!(f64);
-impl_unsigned_4d_data_point!(u8);
-impl_unsigned_4d_data_point!(u16);
-impl_unsigned_4d_data_point!(u32);
-impl_unsigned_4d_data_point!(u64);
-impl_unsigned_4d_data_point!(usize);
-impl_signed_4d_data_point!(i8);
-impl_signed_4d_data_point!(i16);
-impl_signed_4d_data_point!(i32);
-impl_signed_4d_data_point!(i64);
-impl_signed_4d_data_point!(isize);
\ No newline at end of file
+/// `glam`'s 2D vectors, with the `glam` feature. Distances are worked out in `f32`, as `glam` does,
+/// and the mean is the mean of each component.
+#[cfg(feature = "glam")]
+impl DataPoint for glam::Vec2 {
+    fn dist(&self, other: &Self) -> f64 {
+        self.distance(*other) as f64
+    }
+
+    fn dist_sq(&self, other: &Self) -> f64 {
+        self.distance_squared(*other) as f64
+    }
+
+    fn mean(ps: &[Self]) -> Option<Self> {
+        if ps.is_empty() {
+            return None;
+        }
+
+        Some(Self::from_array(array_mean(ps.iter().map(|p| p.to_array()))))
+    }
+
+    fn tie_order(&self, other: &Self) -> Ordering {
+        self.to_array().tie_order(&other.to_array())
+    }
+}
+
+/// `glam`'s 3D vectors, with the `glam` feature, in the same way as `Vec2`
+#[cfg(feature = "glam")]
+impl DataPoint for glam::Vec3 {
+    fn dist(&self, other: &Self) -> f64 {
+        self.distance(*other) as f64
+    }
+
+    fn dist_sq(&self, other: &Self) -> f64 {
+        self.distance_squared(*other) as f64
+    }
+
+    fn mean(ps: &[Self]) -> Option<Self> {
+        if ps.is_empty() {
+            return None;
+        }
+
+        Some(Self::from_array(array_mean(ps.iter().map(|p| p.to_array()))))
+    }
+
+    fn tie_order(&self, other: &Self) -> Ordering {
+        self.to_array().tie_order(&other.to_array())
+    }
+}
+
+/// `ndarray` vectors of any length, with the `ndarray` feature. All the points being clustered
+/// should have the same length.
+#[cfg(feature = "ndarray")]
+impl DataPoint for ndarray::Array1<f64> {
+    fn dist(&self, other: &Self) -> f64 {
+        f64::sqrt(self.dist_sq(other))
+    }
+
+    fn dist_sq(&self, other: &Self) -> f64 {
+        debug_assert_eq!(self.len(), other.len(), "points have different dimensions");
+        self.iter().zip(other.iter()).map(|(a, b)| (a - b).powi(2)).sum()
+    }
+
+    fn mean(ps: &[Self]) -> Option<Self> {
+        let mut sum = ndarray::Array1::zeros(ps.first()?.len());
+        for p in ps.iter() {
+            debug_assert_eq!(p.len(), sum.len(), "points have different dimensions");
+            sum += p;
+        }
+
+        Some(sum / ps.len() as f64)
+    }
+
+    fn tie_order(&self, other: &Self) -> Ordering {
+        lexicographic_order(self.iter().cloned(), other.iter().cloned())
+    }
+}
@@ -0,0 +1,228 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use rand::prelude::*;
+use crate::{_cluster, _fill_empty_clusters, _weighted_kmeans_plus_plus, CycleCheck, StoppingCriteria};
+use crate::metric::Euclidean;
+use crate::types::*;
+
+/// Clustering algorithm using k-means++ where each of the `datapoints` comes with a weight, and
+/// counts as if it were there that many times.
+///
+/// Points are assigned to the nearest centroid as usual, but each centroid is recalculated as the
+/// `WeightedMean` of its points, and k-means++ picks points in proportion to their weight times
+/// their squared distance. A point with a weight of 2 gives the same clustering as the point
+/// appearing twice, without the cost of storing and measuring it twice.
+///
+/// The returned clusters hold the points without their weights.
+pub fn weighted_kmeans<P: WeightedMean>(k: usize, datapoints: Vec<(P, f64)>) -> Vec<Cluster<P>> {
+    if k == 0 || datapoints.is_empty() {
+        return vec![];
+    }
+
+    let (points, weights): (Vec<P>, Vec<f64>) = datapoints.into_iter().unzip();
+    let clusters = _weighted_kmeans_plus_plus(k, points.iter().collect(), weights.clone(), &Euclidean, &mut thread_rng());
+    _weighted_lloyd(clusters.into_iter().map(Cluster::into_centroids).collect(), &points, &weights, StoppingCriteria::default())
+}
+
+/// Combines two clusterings, of separate shards of the data say, into `k` clusters.
+///
+/// The centroids of the clusters in `a` and `b` are themselves clustered, as in `weighted_kmeans`,
+/// weighted by how many points their clusters have. Each merged centroid is then the mean of all
+/// the points of the clusters merged into it, just as if it had been calculated from the points,
+/// and each merged cluster holds all those points. This is much cheaper than clustering all the
+/// points again, but can only ever put whole clusters together, never split them.
+///
+/// Empty clusters are dropped. If there are only `k` clusters or fewer left between `a` and `b`,
+/// they're all kept as they are.
+pub fn merge_clusters<P: WeightedMean>(a: Vec<Cluster<P>>, b: Vec<Cluster<P>>, k: usize) -> Vec<Cluster<P>> {
+    _merge(a, b, k, &mut thread_rng())
+}
+
+fn _merge<P, R>(a: Vec<Cluster<P>>, b: Vec<Cluster<P>>, k: usize, rng: &mut R) -> Vec<Cluster<P>>
+    where P: WeightedMean, R: Rng + ?Sized
+{
+    let clusters: Vec<Cluster<P>> = a.into_iter().chain(b).filter(|c| !c.points.is_empty()).collect();
+    if k == 0 {
+        return vec![];
+    }
+    if clusters.len() <= k {
+        return clusters;
+    }
+
+    let centroids: Vec<P> = clusters.iter().map(|c| c.centroid.clone()).collect();
+    let sizes: Vec<f64> = clusters.iter().map(|c| c.size() as f64).collect();
+    let seeds = _weighted_kmeans_plus_plus(k, centroids.iter().collect(), sizes.clone(), &Euclidean, rng);
+    let merged = _weighted_lloyd_indices(seeds.into_iter().map(Cluster::into_centroids).collect(), &centroids, &sizes, StoppingCriteria::default());
+
+    let mut labels = vec![0; clusters.len()];
+    for (label, m) in merged.iter().enumerate() {
+        for i in m.point_indices.iter() {
+            labels[*i] = label;
+        }
+    }
+
+    let mut merged: Vec<Cluster<P>> = merged.into_iter().map(|m| Cluster::new(&m.centroid)).collect();
+    for (cluster, label) in clusters.into_iter().zip(labels) {
+        merged[label].points.extend(cluster.points);
+    }
+    merged
+}
+
+/// `_lloyd`, recentering each cluster on the weighted mean of its points
+fn _weighted_lloyd<P: WeightedMean>(centroids: Vec<P>, datapoints: &[P], weights: &[f64], stop: StoppingCriteria) -> Vec<Cluster<P>> {
+    _weighted_lloyd_indices(centroids, datapoints, weights, stop).iter()
+        .map(|c| Cluster::from_intermediate(c, datapoints))
+        .collect()
+}
+
+/// `_weighted_lloyd`, leaving the clusters as indices into `datapoints`. Like `_lloyd`, a
+/// clustering that goes around in a cycle stops on the state along it with the lowest weighted
+/// inertia.
+fn _weighted_lloyd_indices<P: WeightedMean>(centroids: Vec<P>, datapoints: &[P], weights: &[f64], stop: StoppingCriteria) -> Vec<IntermediateCluster<P>> {
+    let mut clusters: Vec<IntermediateCluster<P>> = centroids.into_iter()
+        .map(|centroid| IntermediateCluster { centroid, point_indices: vec![] })
+        .collect();
+
+    let mut labels: Vec<usize> = datapoints.iter().enumerate()
+        .map(|(i, point)| _cluster(i, point, &mut clusters, &Euclidean))
+        .collect();
+    _fill_empty_clusters(&mut clusters, &mut labels, datapoints, &Euclidean);
+
+    let mut iterations = 0;
+    let mut cycle = CycleCheck::new(&labels);
+    while iterations < stop.max_iter && !stop.past_deadline() {
+        for cluster in clusters.iter_mut() {
+            let members: Vec<&P> = cluster.point_indices.iter().map(|i| &datapoints[*i]).collect();
+            let member_weights: Vec<f64> = cluster.point_indices.iter().map(|i| weights[*i]).collect();
+            // Points that count for nothing can't say where the centroid should be
+            if member_weights.iter().sum::<f64>() > 0.0 {
+                cluster.centroid = P::weighted_mean_refs(&members, &member_weights);
+            }
+            cluster.point_indices.clear();
+        }
+
+        // A refilled cluster can take back the very point it had, which isn't a change
+        let previous = labels.clone();
+        for (i, (label, point)) in labels.iter_mut().zip(datapoints.iter()).enumerate() {
+            *label = _cluster(i, point, &mut clusters, &Euclidean);
+        }
+        _fill_empty_clusters(&mut clusters, &mut labels, datapoints, &Euclidean);
+        iterations += 1;
+
+        if !cycle.lapping() && labels == previous {
+            break;
+        }
+        let inertia = || {
+            clusters.iter()
+                .flat_map(|c| c.point_indices.iter().map(move |i| weights[*i] * datapoints[*i].dist_sq(&c.centroid)))
+                .sum()
+        };
+        if cycle.check(&labels, inertia, || clusters.clone()) {
+            break;
+        }
+    }
+
+    cycle.into_best().unwrap_or(clusters)
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::{_lloyd, _weighted_kmeans_plus_plus, kmeans_with_rng, StoppingCriteria};
+    use crate::metric::Euclidean;
+    use crate::types::Cluster;
+    use super::{_merge, _weighted_lloyd, merge_clusters, weighted_kmeans};
+
+    #[test]
+    fn weight_is_like_duplicating() {
+        let points = vec![(1f64, 2f64), (1.0, 3.0), (2.0, 2.0), (8.0, 4.0), (8.0, 5.0), (9.0, 6.0)];
+        let weights = [1.0, 3.0, 1.0, 2.0, 1.0, 1.0];
+        let duplicated: Vec<(f64, f64)> = points.iter().zip(weights.iter())
+            .flat_map(|(p, w)| core::iter::repeat_n(*p, *w as usize))
+            .collect();
+        let start = vec![(0.0, 0.0), (10.0, 10.0)];
+
+        let weighted = _weighted_lloyd(start.clone(), &points, &weights, StoppingCriteria::default());
+        let unweighted = _lloyd(start, &duplicated, StoppingCriteria::default(), &Euclidean);
+        assert_eq!(Cluster::centroids(&weighted), Cluster::centroids(&unweighted.clusters));
+        assert_eq!(Cluster::centroids(&weighted), vec![(1.2, 2.6), (8.25, 4.75)]);
+
+        let clusters = weighted_kmeans(1, points.into_iter().zip(weights.iter().cloned()).collect());
+        assert_eq!(Cluster::centroids(&clusters), vec![(39.0 / 9.0, 32.0 / 9.0)]);
+
+        // The duplicated points are split between two identical centroids the same way every round
+        let points = vec![(-1f64, -1f64), (-4.0, -3.0), (-4.0, -3.0), (-2.0, 4.0)];
+        let clusters = weighted_kmeans(4, points.iter().map(|p| (*p, 1.0)).collect());
+        assert!(clusters.iter().all(|c| c.points.len() == 1));
+        let clusters = _weighted_lloyd(points.clone(), &points, &[1.0; 4], StoppingCriteria::default());
+        assert_eq!(Cluster::centroids(&clusters), points);
+    }
+
+    #[test]
+    fn breaks_oscillation() {
+        // The integer points from `kmeans`'s own test, which go around in a cycle. Counting each
+        // point once, it should stop on the same state as `_lloyd`.
+        let points = vec![(-1i8, 3i8), (3, -4), (0, 2), (-1, 1), (-1, 2)];
+        let start = vec![(-1, 3), (-1, 1), (-1, 1)];
+        let weighted = _weighted_lloyd(start.clone(), &points, &[1.0; 5], StoppingCriteria::default());
+        let unweighted = _lloyd(start, &points, StoppingCriteria::default(), &Euclidean);
+        assert_eq!(weighted, unweighted.clusters);
+
+        let clusters = weighted_kmeans(3, points.iter().map(|p| (*p, 1.0)).collect());
+        assert_eq!(clusters.iter().map(|c| c.points.len()).sum::<usize>(), points.len());
+
+        // No rounds at all leaves the centroids where they started
+        let stop = StoppingCriteria { max_iter: 0, ..StoppingCriteria::default() };
+        let clusters = _weighted_lloyd(vec![(-1, 3), (-1, 1), (3, -4)], &points, &[1.0; 5], stop);
+        assert_eq!(Cluster::centroids(&clusters), vec![(-1, 3), (-1, 1), (3, -4)]);
+    }
+
+    #[test]
+    fn weighted_seeding() {
+        let points = [(0f64, 0f64), (1.0, 0.0), (50.0, 50.0), (2.0, 0.0)];
+
+        // The far-off point would almost always be picked if it counted for anything
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let clusters = _weighted_kmeans_plus_plus(2, points.iter().collect(), vec![1.0, 1.0, 0.0, 1.0], &Euclidean, &mut rng);
+            assert!(!Cluster::centroids(&clusters).contains(&(50.0, 50.0)));
+        }
+    }
+
+    #[test]
+    fn merge_shards() {
+        let file = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/test_data.txt")).unwrap();
+        let data: Vec<(f64, f64, f64)> = file.trim().lines()
+            .map(|line| {
+                let coords: Vec<f64> = line.split_whitespace().map(|s| s.parse().unwrap()).collect();
+                (coords[0], coords[1], coords[2])
+            })
+            .collect();
+        let (first, second) = data.split_at(data.len() / 2);
+
+        for seed in 0..10 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let a = kmeans_with_rng(2, first.to_vec(), &mut rng);
+            let b = kmeans_with_rng(2, second.to_vec(), &mut rng);
+            let merged = _merge(a, b, 2, &mut rng);
+            let full = kmeans_with_rng(2, data.clone(), &mut rng);
+
+            assert_eq!(merged.iter().map(Cluster::size).sum::<usize>(), data.len());
+            for cluster in merged.iter() {
+                let (x, y, z) = cluster.centroid;
+                assert!(Cluster::centroids(&full).iter().any(|(fx, fy, fz)| {
+                    (x - fx).abs() < 1e-9 && (y - fy).abs() < 1e-9 && (z - fz).abs() < 1e-9
+                }));
+            }
+        }
+
+        // Nothing to merge
+        let clusters = vec![Cluster { centroid: 1.0, points: vec![1.0] }, Cluster { centroid: 2.0, points: vec![] }];
+        assert_eq!(merge_clusters(clusters.clone(), vec![], 2), clusters[..1].to_vec());
+        assert!(merge_clusters(clusters, vec![], 0).is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use rand::prelude::*;
+use crate::{_kmeans, _lloyd, bic, StoppingCriteria};
+use crate::metric::Euclidean;
+use crate::types::*;
+
+/// Clustering algorithm that finds k for itself, by [x-means], returning k along with the
+/// clusters.
+///
+/// It starts with k-means++ for `k_min` clusters. Then each cluster is split in two by running
+/// 2-means on just its points, and the split is kept if the two halves have a higher `bic` on
+/// those points than the cluster does alone. If any were split, k-means runs again on all the
+/// points from the centroids that are left, and this repeats until no split helps or there are
+/// `k_max` clusters. Of all the clusterings along the way, the one with the highest `bic` is
+/// returned.
+///
+/// `k_min` is raised to 1 if it's 0. With no points, or a `k_max` of 0, the result is
+/// `(0, vec![])`.
+///
+/// [x-means]: https://www.cs.cmu.edu/~dpelleg/download/xmeans.pdf
+pub fn xmeans<P: DataPoint + Coordinates>(datapoints: Vec<P>, k_min: usize, k_max: usize) -> (usize, Vec<Cluster<P>>) {
+    _xmeans(datapoints, k_min, k_max, &mut thread_rng())
+}
+
+fn _xmeans<P, R>(datapoints: Vec<P>, k_min: usize, k_max: usize, rng: &mut R) -> (usize, Vec<Cluster<P>>)
+    where P: DataPoint + Coordinates, R: Rng + ?Sized
+{
+    let k_max = k_max.min(datapoints.len());
+    if k_max == 0 {
+        return (0, vec![]);
+    }
+
+    let mut clusters = _kmeans(k_min.clamp(1, k_max), &datapoints, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &Euclidean, rng).clusters;
+    let mut best = (bic(&clusters), clusters.clone());
+    loop {
+        // Split whichever clusters are better explained as two
+        let mut centroids = Vec::with_capacity(k_max);
+        for (i, cluster) in clusters.iter().enumerate() {
+            // Leave room for the clusters still to come, and don't try to split a single point
+            if centroids.len() + clusters.len() - i < k_max && cluster.points.len() >= 2 {
+                let halves = _kmeans(2, &cluster.points, InitMethod::KMeansPlusPlus, StoppingCriteria::default(), &Euclidean, rng).clusters;
+                if bic(&halves) > bic(core::slice::from_ref(cluster)) {
+                    centroids.extend(halves.into_iter().map(Cluster::into_centroids));
+                    continue;
+                }
+            }
+            centroids.push(cluster.centroid.clone());
+        }
+        if centroids.len() == clusters.len() {
+            break;
+        }
+
+        clusters = _lloyd(centroids, &datapoints, StoppingCriteria::default(), &Euclidean).clusters;
+        let score = bic(&clusters);
+        if score > best.0 {
+            best = (score, clusters.clone());
+        }
+    }
+    (best.1.len(), best.1)
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "alloc")]
+    use alloc::vec::Vec;
+
+    use rand::{rngs::StdRng, SeedableRng};
+    use crate::{bic, kmeans_seeded};
+    use super::{_xmeans, xmeans};
+
+    fn fixture() -> Vec<(f64, f64, f64)> {
+        let file = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/test_data.txt")).unwrap();
+        file.trim().lines()
+            .map(|line| {
+                let coords: Vec<f64> = line.split_whitespace().map(|s| s.parse().unwrap()).collect();
+                (coords[0], coords[1], coords[2])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn two_blobs() {
+        let data = fixture();
+        for seed in 0..10 {
+            let scores: Vec<f64> = (1..=3).map(|k| bic(&kmeans_seeded(k, data.clone(), seed))).collect();
+            assert!(scores[1] > scores[0] && scores[1] > scores[2], "{:?}", scores);
+
+            let (k, clusters) = _xmeans(data.clone(), 1, 6, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(k, 2);
+            assert_eq!(clusters.len(), 2);
+            assert_eq!(clusters.iter().map(|c| c.size()).sum::<usize>(), data.len());
+        }
+
+        // Held to one cluster, there's nothing to choose
+        assert_eq!(xmeans(data.clone(), 1, 1).0, 1);
+        assert_eq!(xmeans(Vec::<(f64, f64)>::new(), 1, 5), (0, vec![]));
+    }
+
+    #[test]
+    fn singleton_cluster() {
+        // The far-off point is a cluster on its own, with nothing to split
+        let points = vec![(0f64, 0f64), (0.0, 1.0), (1.0, 0.0), (1.0, 1.0), (100.0, 100.0)];
+        for seed in 0..10 {
+            let (k, clusters) = _xmeans(points.clone(), 2, 4, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(k, clusters.len());
+            assert!((2..=4).contains(&k));
+            assert_eq!(clusters.iter().map(|c| c.size()).sum::<usize>(), points.len());
+        }
+
+        let points = vec![(0, 0), (0, 1), (1, 0), (1, 1), (100, 100)];
+        assert_eq!(xmeans(points.clone(), 2, 4).1.iter().map(|c| c.size()).sum::<usize>(), points.len());
+    }
+}